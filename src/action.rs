@@ -0,0 +1,508 @@
+// The vocabulary that front-ends use to talk to the game:
+// `Action`s go in, `GameEvent`s (or a `RuleError`) come out.
+use crate::market::{City, Drug};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Everything a player can ask the game to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Buy { drug: Drug, qty: i32 },          // Buy drugs at the local price
+    Sell { drug: Drug, qty: i32 },         // Sell drugs at the local price
+    Travel(City),                          // Move to a neighbouring city (takes a day or more)
+    PayDebt(i32),                          // Pay some of the loan shark debt
+    Borrow { lender: usize, amount: i32 }, // Borrow more, up to the credit limit
+    Deposit(i32),                          // Put cash in the bank (only in the bank's city)
+    Withdraw(i32),                         // Take cash out of the bank (only in the bank's city)
+    Stash { drug: Drug, qty: i32 },        // Leave drugs at the stash house (only in its city)
+    Unstash { drug: Drug, qty: i32 },      // Take drugs from the stash house (only in its city)
+    UpgradeStash,                          // Make the stash house bigger
+    HireHenchman,                          // Take on a henchman (who wants paying every day)
+    DismissHenchman,                       // Let a henchman go
+    ShopAround,                            // Visit the black market for today's offers
+    ShopBuy(ShopItem),                     // Buy one of the black market offers
+    LeaveShop,                             // Walk away from the black market
+    AcceptOffer,                           // Buy what a stranger is offering
+    DeclineOffer,                          // Turn a stranger down
+    Fight,                                 // Attack during an encounter
+    Run,                                   // Try to escape an encounter
+    Surrender,                             // Give up to the cops (and go to jail)
+    Bribe,                                 // Pay the cops to look the other way
+    DropDrugs,                             // Drop some drugs for rival dealers to go after
+    Hide,                                  // Try to lie low until the enemy gives up
+}
+
+// The items that the black market sells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShopItem {
+    TrenchCoat,    // +50 space
+    Weapon(usize), // A weapon from the catalogue (a number into the rules' weapons)
+    Ammo,          // A box of ammunition for firearms
+    Armor(usize),  // Body armor from the catalogue (a number into the rules' armor)
+    Medicine,      // Restores health to full
+}
+
+// The prices on offer during one visit to the black market
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShopOffer {
+    pub coat_price: i32,
+    pub weapon_prices: Vec<i32>, // The price of every weapon in the catalogue, in order
+    pub med_price: i32,
+    pub ammo_price: i32, // For a box of ammunition
+    // The price of every piece of armor in the catalogue, in order
+    pub armor_prices: Vec<i32>,
+}
+
+impl ShopOffer {
+    // For returning the offered price of an item, if it is for sale
+    pub fn price(&self, item: ShopItem) -> Option<i32> {
+        match item {
+            ShopItem::TrenchCoat => Some(self.coat_price),
+            ShopItem::Weapon(weapon) => self.weapon_prices.get(weapon).copied(),
+            ShopItem::Ammo => Some(self.ammo_price),
+            ShopItem::Armor(armor) => self.armor_prices.get(armor).copied(),
+            ShopItem::Medicine => Some(self.med_price),
+        }
+    }
+}
+
+// Something that a stranger offers the player on the way into town
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrangerOffer {
+    pub item: ShopItem, // A bigger trench coat or a weapon
+    pub price: i32,
+}
+
+// The parties that may pick a fight with the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Enemy {
+    Cops,
+    RivalDealers,
+    Enforcers, // Sent by a lender to collect an overdue debt
+}
+
+impl Enemy {
+    // For returning the display name of the enemy
+    pub fn name(&self) -> &'static str {
+        match self {
+            Enemy::Cops => "Cops",
+            Enemy::RivalDealers => "Rival Dealers",
+            Enemy::Enforcers => "Enforcers",
+        }
+    }
+    // The enemy's starting health
+    pub(crate) fn health(&self) -> i32 {
+        match self {
+            Enemy::Cops => 15,
+            Enemy::RivalDealers => 10,
+            Enemy::Enforcers => 12,
+        }
+    }
+    // How many of them show up
+    pub(crate) fn count(&self) -> i32 {
+        match self {
+            Enemy::Cops => 2,
+            Enemy::RivalDealers => 1,
+            Enemy::Enforcers => 2,
+        }
+    }
+    // The news flash announcing the encounter
+    pub(crate) fn headline(&self) -> &'static str {
+        match self {
+            Enemy::Cops => "Officer Hardass and his deputies are on a donut break... but spot you!",
+            Enemy::RivalDealers => "Rival drug dealers challenge you to a turf war!",
+            Enemy::Enforcers => "Two large gentlemen step out of an alley: 'You're late, pal.'",
+        }
+    }
+    // The funny lines that may be shouted during a round
+    pub(crate) fn taunts(&self) -> &'static [&'static str] {
+        match self {
+            Enemy::Cops => &[
+                // "Officer Hardass yells: 'Freeze, scumbag!'",
+                // "A deputy drops his donut and draws his gun!",
+                // "The police radio blares: 'Suspect is armed and fabulous!'",
+                "A deputy drops his donut and draws his gun, only to trip on the sidewalk and face-plant.",
+                "A deputy drops his donut and draws his gun!",
+                "A deputy gets his badge stuck in a tree: 'This is not how I envisioned my career.'",
+                "A patrol car screeches to a halt: 'We have a situation... of epic proportions!'",
+                "A police officer tries to intimidate the suspect by using a fake mustache, but ends up looking ridiculous instead.",
+                "A rookie cop accidentally arrests a man who looks just like him, leading to an awkward exchange.",
+                "An officer claims to have 'expertly' handcuffed the suspect, only for them to easily slip out of the cuffs.",
+                "An officer gets stuck in the doorway of the suspect's car and has to be pulled out by two other officers.",
+                "Officer Bob mistakes a bag of chips for a stash of drugs and starts searching it with a magnifying glass.",
+                "Officer Hardass yells: 'Freeze, scumbag! But first, let me check my clipboard...'",
+                "Officer Hardass yells: 'Freeze, scumbag!'",
+                "Officer Johnson says: 'I've got you surrounded, suspect... on the other side of this building.'",
+                "Officer Jones shouts: 'I'm not searching you, I'm just... um... admiring your vehicle!'",
+                "Officer Smith barks into the mic: 'What's this? A warrant? No, no, no! I was just, uh, conducting research!'",
+                "The police car gets stuck in the parking lot due to the officer's ineptitude at parallel parking.",
+                "The police chief yells: 'Code 55: Code 55! That means we're out of donuts.'",
+                "The police radio blares: 'Suspect is armed and fabulous! Can we also order a box of donuts?'",
+                "The police radio blares: 'Suspect is armed and fabulous!'",
+                "The police radio crackles: 'All units, we have a report of suspicious activity... like someone eating an entire pizza by themselves.'",
+                "The police sirens are so loud that they shatter the suspect's sunglasses.",
+            ],
+            Enemy::RivalDealers => &[
+                "A rival yells: 'This is our block now!'",
+                "Someone throws a bag of oregano at you!",
+                "A dealer shouts: 'You call that product?'",
+                "Looks like someone's supply ran out... of dignity!",
+                "I see you're still peddling the same old trash, dude.",
+                "You must have misspelled ' failure' on your storefront sign!",
+                "I heard your product is so bad, it needs its own hazmat suit!",
+                "Looks like you left the competition to me... and my amazing deals!",
+                "Your operation looks like a 3rd-grader's art project gone wrong",
+                "Is that a 'Closed' sign or just a prayer?",
+                "I'm starting a betting pool on how long it takes for you to get shut down.",
+                "You know what they say: 'you can't buy happiness, but I heard they're selling it cheap at your store'",
+                "It looks like someone's trying out for the role of ' failed entrepreneur'... nice try!",
+                "Your reputation is so shot, I think it's still in rehab",
+                "Looks like you took the phrase 'on the rocks' too literally",
+                "I heard your product is so old, it's been known to be used as bookends",
+                "You must have hired a team of experts... at losing",
+                "This block? I think it's still on rent. You're just squatting",
+            ],
+            Enemy::Enforcers => &[
+                "An enforcer cracks his knuckles: 'Nothing personal. Well, a little personal.'",
+                "'The boss says hi. And that the interest is still running.'",
+                "An enforcer checks his notebook: 'Says here you've got two good legs. For now.'",
+                "'We accept cash, drugs, and regret.'",
+                "An enforcer sighs: 'I had tickets to the ballet tonight, you know.'",
+            ],
+        }
+    }
+}
+
+// The direction of a sudden price change in a news flash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceChange {
+    Drop(i32), // Percent off
+    Rise(i32), // Percent up
+}
+
+// Everything that can happen in the game, in the order it happened.
+// Front-ends decide how (and whether) to show each of these.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    Bought {
+        drug: Drug,
+        qty: i32,
+        cost: i32,
+    },
+    Sold {
+        drug: Drug,
+        qty: i32,
+        revenue: i32,
+    },
+    Travelled {
+        city: City,
+        day: i32,
+        cost: i32,
+    },
+    InterestApplied {
+        interest: i32,
+        rate: f32,
+        debt: i32,
+    },
+    DebtPaid {
+        amount: i32,
+        remaining: i32,
+    },
+    Borrowed {
+        lender: String,
+        amount: i32,
+        debt: i32,
+    },
+    // The lender reminds the player that the debt is overdue
+    LoanWarning {
+        lender: String,
+        debt: i32,
+    },
+    // The lender's enforcers take cash towards the debt
+    DebtCollected {
+        lender: String,
+        amount: i32,
+        debt: i32,
+    },
+    // The lender's enforcers take drugs for their trouble
+    DrugsSeized {
+        lender: String,
+        drug: Drug,
+        qty: i32,
+    },
+    BonesBroken {
+        lender: String,
+        damage: i32,
+        health: i32,
+    },
+    Deposited {
+        amount: i32,
+        balance: i32,
+    },
+    Withdrawn {
+        amount: i32,
+        balance: i32,
+    },
+    BankInterest {
+        interest: i32,
+        rate: f32,
+        balance: i32,
+    },
+    Stashed {
+        drug: Drug,
+        qty: i32,
+        stashed: i32,
+    },
+    Unstashed {
+        drug: Drug,
+        qty: i32,
+        stashed: i32,
+    },
+    StashUpgraded {
+        space: i32,
+        cost: i32,
+    },
+    HenchmanHired {
+        fee: i32,
+        henchmen: i32, // How many henchmen the player now has
+    },
+    HenchmanDismissed {
+        henchmen: i32,
+    },
+    WagesPaid {
+        henchmen: i32, // How many henchmen got paid
+        wages: i32,
+    },
+    // There wasn't the cash to pay some of the henchmen, and some of those walked off
+    WagesUnpaid {
+        unpaid: i32,
+        deserted: i32,
+        henchmen: i32, // How many henchmen are left
+    },
+    // The cops find the stash house and take a share of every drug in it
+    StashRaided {
+        city: City,
+        seized: Vec<(Drug, i32)>,
+    },
+    // Someone grabs a share of the cash on hand
+    Mugged {
+        amount: i32,
+        cash: i32,
+    },
+    // Drugs lying around, free to whoever has room for them
+    FoundDrugs {
+        drug: Drug,
+        qty: i32,
+    },
+    OfferMade(StrangerOffer),
+    OfferDeclined,
+    // A friend passes on the going price of a drug somewhere else
+    Tip {
+        drug: Drug,
+        city: City,
+        price: i32,
+    },
+    ShopOpened(ShopOffer),
+    ShopLeft,
+    CoatBought {
+        space: i32,
+    },
+    WeaponBought {
+        weapon: String,
+        weapons: i32, // How many weapons the player now carries
+    },
+    AmmoBought {
+        rounds: i32, // How many rounds the player now carries
+    },
+    ArmorBought {
+        armor: String,
+    },
+    MedicineBought {
+        health: i32,
+    },
+    NewsFlash {
+        headline: String,
+        drug: Drug,
+        change: PriceChange,
+        price: i32,
+    },
+    // Nobody will deal a drug in a city for some days (counting today)
+    Shortage {
+        drug: Drug,
+        city: City,
+        days: i32,
+    },
+    EncounterStarted {
+        enemy: Enemy,
+        headline: String,
+    },
+    FightStarted {
+        enemy: Enemy,
+        count: i32,
+    },
+    RoundStarted {
+        round: i32,
+        taunt: Option<String>,
+        player_health: i32,
+        enemy: Enemy,
+        enemy_health: i32,
+    },
+    PlayerHit {
+        weapon: String,
+        damage: i32,
+    },
+    PlayerMissed {
+        weapon: String,
+    },
+    // The last of the ammunition that a firearm takes has been used up
+    OutOfAmmo {
+        weapon: String,
+    },
+    // A melee weapon has worn out and is thrown away
+    WeaponBroke {
+        weapon: String,
+    },
+    EnemyHit {
+        enemy: Enemy,
+        damage: i32,   // The damage that got through to the player
+        absorbed: i32, // Damage soaked up by the player's armor
+    },
+    HenchmanHit {
+        damage: i32,
+    },
+    HenchmanMissed,
+    HenchmanWounded {
+        enemy: Enemy,
+        damage: i32,
+        health: i32, // The henchman's health left
+    },
+    HenchmanKilled {
+        enemy: Enemy,
+        henchmen: i32, // How many henchmen are left
+    },
+    // The player's armor has soaked up all that it can and falls apart
+    ArmorBroke {
+        armor: String,
+    },
+    EnemyDefeated {
+        enemy: Enemy,
+        loot: i32,
+    },
+    PlayerDefeated {
+        enemy: Enemy,
+    },
+    // The cops take the player in, confiscate their drugs and some of their cash, and lock them
+    // up for some days
+    Arrested {
+        resisted: bool,
+        days: i32,
+        cash: i32,
+        seized: Vec<(Drug, i32)>,
+    },
+    Released {
+        day: i32,
+    },
+    Escaped,
+    EscapeFailed {
+        damage: i32,
+        absorbed: i32,
+    },
+    Bribed {
+        cash: i32,
+    },
+    BribeRefused {
+        damage: i32,
+        absorbed: i32,
+    },
+    // A share of every drug carried, left behind for rival dealers to fight over
+    DrugsDropped {
+        dropped: Vec<(Drug, i32)>,
+    },
+    Distracted,
+    DistractionFailed {
+        damage: i32,
+        absorbed: i32,
+    },
+    Hidden,
+    HideFailed {
+        damage: i32,
+        absorbed: i32,
+    },
+}
+
+// The reasons that the game may refuse an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleError {
+    GameOver,         // The game has already ended
+    InEncounter,      // Only fighting or a way out of the fight is allowed now
+    NoEncounter,      // There is nobody to fight or run from
+    NoSurrender,      // Only the cops take prisoners
+    NoBribe,          // Only the cops take bribes
+    NoDistraction,    // Only rival dealers go after dropped drugs
+    NothingToDrop,    // No drugs in the trench coat to drop
+    Unarmed,          // No weapon that can still be used
+    NoShop,           // The black market is not open
+    NoOffer,          // Nobody is offering the player anything
+    UnknownDrug,      // That drug is not sold on this market
+    SoldOut,          // Nobody is dealing that drug here today
+    NoRoute,          // There is no way to get there from here
+    InvalidQuantity,  // Quantities and amounts must be positive
+    NotEnoughCash,    // The player cannot afford it
+    NotEnoughSpace,   // The trench coat is full
+    NotEnoughDrugs,   // The player does not have that many to sell
+    OverPayment,      // Paying back more than is owed
+    UnknownLender,    // There is no such lender
+    UnknownWeapon,    // The black market doesn't sell that weapon or armor
+    OtherLender,      // The debt is owed to someone else, who has to be paid off first
+    OverCreditLimit,  // Borrowing more than the credit limit allows
+    NoBank,           // There is no bank in this city
+    NotEnoughSaved,   // Withdrawing more than is in the bank
+    NoStash,          // The stash house is in another city
+    StashFull,        // The stash house has no room for that much
+    NotEnoughStashed, // Taking more out of the stash house than is in it
+    HealthFull,       // Medicine would do nothing
+    CrewFull,         // The player has as many henchmen as they can take on
+    NoHenchmen,       // There are no henchmen to let go
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            RuleError::GameOver => "The game is over.",
+            RuleError::InEncounter => "You are in the middle of a fight!",
+            RuleError::NoEncounter => "There is nobody to fight.",
+            RuleError::NoSurrender => "They're not taking prisoners!",
+            RuleError::NoBribe => "They're not for sale!",
+            RuleError::NoDistraction => "They're not after your drugs!",
+            RuleError::NothingToDrop => "You don't have any drugs on you to drop!",
+            RuleError::Unarmed => "You have nothing left to fight with!",
+            RuleError::NoShop => "The black market is closed.",
+            RuleError::NoOffer => "Nobody is offering you anything.",
+            RuleError::UnknownDrug => "Nobody around here sells that.",
+            RuleError::SoldOut => "Nobody is dealing that here today.",
+            RuleError::NoRoute => "You can't get there from here.",
+            RuleError::InvalidQuantity => "Invalid amount.",
+            RuleError::NotEnoughCash => "Not enough cash.",
+            RuleError::NotEnoughSpace => "Not enough space.",
+            RuleError::NotEnoughDrugs => "Not enough to sell.",
+            RuleError::OverPayment => "You don't owe that much!",
+            RuleError::UnknownLender => "Nobody by that name lends money around here.",
+            RuleError::UnknownWeapon => "The black market doesn't sell that.",
+            RuleError::OtherLender => {
+                "Pay off what you owe first. Nobody wants to cross your lender."
+            }
+            RuleError::OverCreditLimit => "They won't lend you that much.",
+            RuleError::NoBank => "There's no bank here.",
+            RuleError::NotEnoughSaved => "You don't have that much in the bank.",
+            RuleError::NoStash => "Your stash house is in another city.",
+            RuleError::StashFull => "There's no room for that in your stash house.",
+            RuleError::NotEnoughStashed => "You don't have that many stashed.",
+            RuleError::HealthFull => "Your health is already full!",
+            RuleError::CrewFull => "You can't keep any more henchmen in line.",
+            RuleError::NoHenchmen => "You don't have any henchmen.",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for RuleError {}
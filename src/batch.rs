@@ -0,0 +1,90 @@
+// The `simulate` subcommand: plays thousands of games with a computer strategy and prints how
+// they went, for tuning the game's balance with data.
+use crate::{colour_print, load_rules, usage_and_exit};
+use drugwars_rs::{Distribution, STRATEGY_NAMES, SimulationReport, simulate, strategy_by_name};
+
+// Reads the subcommand's options, runs the games and prints the report
+pub fn main(mut args: impl Iterator<Item = String>) {
+    let mut games: usize = 1000;
+    let mut seed: u64 = rand::random();
+    let mut strategy = String::from("random");
+    let mut rules_file = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(count) => games = count,
+                None => usage_and_exit("--games needs a whole number"),
+            },
+            "--seed" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(first_seed) => seed = first_seed,
+                None => usage_and_exit("--seed needs a whole number"),
+            },
+            "--rules" => match args.next() {
+                Some(path) => rules_file = Some(path),
+                None => usage_and_exit("--rules needs a file name"),
+            },
+            "--strategy" => match args.next() {
+                Some(name) if STRATEGY_NAMES.contains(&name.as_str()) => strategy = name,
+                _ => usage_and_exit(&format!(
+                    "--strategy needs one of: {}",
+                    STRATEGY_NAMES.join(", ")
+                )),
+            },
+            _ => usage_and_exit(&format!("Unknown option '{}'", arg)),
+        }
+    }
+
+    let rules = load_rules(rules_file.as_deref());
+    colour_print(
+        &format!(
+            "\t Simulating {} games with the '{}' strategy (seeds {} onwards)...",
+            games, strategy, seed
+        ),
+        "purple",
+    );
+    let report = simulate(games, seed, &rules, |game_seed| {
+        strategy_by_name(&strategy, game_seed).expect("strategy name was checked above")
+    })
+    .expect("the rules were checked when they were loaded");
+    print_report(&report);
+}
+
+// Prints the summary of a batch of games
+fn print_report(report: &SimulationReport) {
+    colour_print(
+        &format!("\n\t Results for {} games:", report.games.len()),
+        "cyan",
+    );
+    println!(
+        "\t {:<12} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "", "mean", "min", "p10", "p25", "median", "p75", "p90", "max"
+    );
+    print_distribution("Final cash", &report.final_cash);
+    print_distribution("Score", &report.score);
+    print_distribution("Days played", &report.days);
+    println!();
+    print_rate("Death rate", report.death_rate);
+    print_rate("Debt-out rate", report.debt_out_rate);
+    print_rate("Stalled", report.stalled_rate);
+}
+
+// Prints one row of the results table
+fn print_distribution(label: &str, distribution: &Distribution) {
+    println!(
+        "\t {:<12} {:>10.1} {:>10.0} {:>10.0} {:>10.0} {:>10.0} {:>10.0} {:>10.0} {:>10.0}",
+        label,
+        distribution.mean,
+        distribution.min,
+        distribution.p10,
+        distribution.p25,
+        distribution.median,
+        distribution.p75,
+        distribution.p90,
+        distribution.max
+    );
+}
+
+// Prints a share of the games as a percentage
+fn print_rate(label: &str, rate: f64) {
+    println!("\t {:<14} {:>6.1}%", label, rate * 100.0);
+}
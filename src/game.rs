@@ -0,0 +1,1283 @@
+// The game state machine: `Game::apply` takes one `Action` at a time, checks it against the
+// rules and returns everything that happened as a list of `GameEvent`s.
+use crate::action::{Action, Enemy, GameEvent, RuleError, ShopItem, ShopOffer, StrangerOffer};
+use crate::market::{City, CityInfo, Drug, Market, Route};
+use crate::player::{Armor, Player, Weapon};
+use crate::random_events::EventRegistry;
+use crate::rules::{Rules, RulesError, SHOP_PRICE_SWING};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+// What the black market charges for the things that are not in the rules' catalogues
+pub const COAT_PRICES: RangeInclusive<i32> = 1000..=4000;
+pub const MEDICINE_PRICES: RangeInclusive<i32> = 1000..=4000;
+pub const AMMO_PRICES: RangeInclusive<i32> = 500..=1500; // For a box of ammunition
+
+// A fight that is waiting for the player to (f)ight or (r)un
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Encounter {
+    pub enemy: Enemy,      // Who the player is up against
+    pub enemy_health: i32, // How much fight they have left
+    pub enemy_count: i32,  // How many of them there are
+    pub round: i32,        // The current round
+}
+
+// Struct for representing the overall game state
+pub struct Game {
+    pub player: Player,                      // The player
+    pub markets: HashMap<City, Market>,      // The drug market in every city
+    pub(crate) rules: Rules,                 // The rules that this game is played by
+    pub(crate) seed: u64,                    // The seed that the whole game was started from
+    pub(crate) rng: ChaCha8Rng,              // Random number generator behind every random decision
+    pub(crate) encounter: Option<Encounter>, // The fight in progress, if any
+    pub(crate) shop: Option<ShopOffer>,      // The black market offers, while visiting
+    pub(crate) offer: Option<StrangerOffer>, // A stranger's offer, until the player moves on
+    pub(crate) random_events: EventRegistry, // The random events that can happen on arrival
+}
+
+impl Game {
+    // Creates a new game with a new player, initial prices and a random seed
+    pub fn new() -> Self {
+        Game::with_seed(rand::thread_rng().r#gen())
+    }
+
+    // Creates a new game whose every random decision follows from `seed`,
+    // so the same seed and the same actions always play out the same way
+    pub fn with_seed(seed: u64) -> Self {
+        Game::with_rules(seed, Rules::default()).expect("the default rules are valid")
+    }
+
+    // Creates a new game from `seed` that is played by the given rules, once they have been
+    // checked the same way as the rules read from a file
+    pub fn with_rules(seed: u64, rules: Rules) -> Result<Self, RulesError> {
+        rules.validate()?;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let markets = rules
+            .cities
+            .iter()
+            .map(|city| (city.name, Market::new(&rules, city, &mut rng)))
+            .collect();
+        Ok(Game {
+            player: Player::new(&rules),
+            markets,
+            rules,
+            seed,
+            rng,
+            encounter: None,
+            shop: None,
+            offer: None,
+            random_events: EventRegistry::standard(),
+        })
+    }
+
+    // The seed that this game was started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // The rules that this game is played by
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    // The fight in progress, if any. While there is one, only `Fight`, `Run`, `Hide`, `Bribe`
+    // and `Surrender` (against the cops) and `DropDrugs` (against rival dealers) are accepted.
+    pub fn encounter(&self) -> Option<&Encounter> {
+        self.encounter.as_ref()
+    }
+
+    // The black market offers, while the player is visiting it
+    pub fn shop(&self) -> Option<&ShopOffer> {
+        self.shop.as_ref()
+    }
+
+    // What a stranger is offering the player, until they do anything else
+    pub fn offer(&self) -> Option<&StrangerOffer> {
+        self.offer.as_ref()
+    }
+
+    // The random events that can happen on arrival
+    pub fn random_events(&self) -> &EventRegistry {
+        &self.random_events
+    }
+
+    // The random events, for adding, removing or replacing some. They are not saved, so a
+    // loaded game starts out with the standard ones again.
+    pub fn random_events_mut(&mut self) -> &mut EventRegistry {
+        &mut self.random_events
+    }
+
+    // The random number generator behind every random decision. Random events that are added
+    // from outside should draw from it too, so that seeded games stay reproducible.
+    pub fn rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+
+    // The drug market in the city that the player is in
+    pub fn market(&self) -> &Market {
+        &self.markets[&self.player.city]
+    }
+
+    // Today's drug prices in the city that the player is in
+    pub fn prices(&self) -> &HashMap<Drug, i32> {
+        &self.market().prices
+    }
+
+    // The routes out of the city that the player is in
+    pub fn routes(&self) -> &[Route] {
+        &self.here().routes
+    }
+
+    // The most units of a drug that the player can afford and carry
+    pub fn max_buyable(&self, drug: Drug) -> i32 {
+        let (Some(price), Some(info)) =
+            (self.market().exact_price(drug), self.rules.drug_info(drug))
+        else {
+            return 0;
+        };
+        if !self.market().is_available(drug) {
+            return 0;
+        }
+        let max_by_space = self.player.free_space(&self.rules) / info.space;
+        info.max_affordable(price, self.player.cash, max_by_space)
+    }
+
+    // What buying some units of a drug here would cost in all, as the price rises with each one
+    pub fn buy_cost(&self, drug: Drug, qty: i32) -> Option<i32> {
+        let info = self.rules.drug_info(drug)?;
+        Some(info.buy_cost(self.market().exact_price(drug)?, qty))
+    }
+
+    // What selling some units of a drug here would bring in, as the price falls with each one
+    pub fn sell_revenue(&self, drug: Drug, qty: i32) -> Option<i32> {
+        let info = self.rules.drug_info(drug)?;
+        Some(info.sell_revenue(self.market().exact_price(drug)?, qty))
+    }
+
+    // Applies one player action and returns what happened.
+    // On error the game state is left untouched.
+    pub fn apply(&mut self, action: Action) -> Result<Vec<GameEvent>, RuleError> {
+        if self.is_game_over() {
+            return Err(RuleError::GameOver);
+        }
+        let mut events = Vec::new();
+        match action {
+            Action::Fight
+            | Action::Run
+            | Action::Surrender
+            | Action::Bribe
+            | Action::DropDrugs
+            | Action::Hide
+                if self.encounter.is_none() =>
+            {
+                return Err(RuleError::NoEncounter);
+            }
+            Action::Fight => self.fight(&mut events)?,
+            Action::Run => self.run(&mut events),
+            Action::Surrender => self.surrender(&mut events)?,
+            Action::Bribe => self.bribe(&mut events)?,
+            Action::DropDrugs => self.drop_drugs(&mut events)?,
+            Action::Hide => self.hide(&mut events),
+            _ if self.encounter.is_some() => return Err(RuleError::InEncounter),
+            Action::Buy { drug, qty } => self.buy(drug, qty, &mut events)?,
+            Action::Sell { drug, qty } => self.sell(drug, qty, &mut events)?,
+            Action::Travel(city) => self.travel(city, &mut events)?,
+            Action::PayDebt(amount) => self.loan_shark(amount, &mut events)?,
+            Action::Borrow { lender, amount } => self.borrow(lender, amount, &mut events)?,
+            Action::Deposit(amount) => self.deposit(amount, &mut events)?,
+            Action::Withdraw(amount) => self.withdraw(amount, &mut events)?,
+            Action::Stash { drug, qty } => self.stash(drug, qty, &mut events)?,
+            Action::Unstash { drug, qty } => self.unstash(drug, qty, &mut events)?,
+            Action::UpgradeStash => self.upgrade_stash(&mut events)?,
+            Action::HireHenchman => self.hire_henchman(&mut events)?,
+            Action::DismissHenchman => {
+                if self.player.henchmen.pop().is_none() {
+                    return Err(RuleError::NoHenchmen);
+                }
+                events.push(GameEvent::HenchmanDismissed {
+                    henchmen: self.player.henchmen.len() as i32,
+                });
+            }
+            Action::ShopAround => self.shop_around(&mut events),
+            Action::ShopBuy(item) => self.shop_buy(item, &mut events)?,
+            Action::LeaveShop => {
+                if self.shop.take().is_none() {
+                    return Err(RuleError::NoShop);
+                }
+                events.push(GameEvent::ShopLeft);
+            }
+            Action::AcceptOffer => self.accept_offer(&mut events)?,
+            Action::DeclineOffer => {
+                if self.offer.take().is_none() {
+                    return Err(RuleError::NoOffer);
+                }
+                events.push(GameEvent::OfferDeclined);
+            }
+        }
+        // Walking away to do anything else closes the black market
+        if !matches!(action, Action::ShopAround | Action::ShopBuy(_)) {
+            self.shop = None;
+        }
+        // ...and turns the stranger down (travelling brings the next day's offer, if any)
+        if !matches!(action, Action::Travel(_)) {
+            self.offer = None;
+        }
+        Ok(events)
+    }
+
+    // The map entry for the city that the player is in
+    pub(crate) fn here(&self) -> &CityInfo {
+        self.rules
+            .city_info(self.player.city)
+            .unwrap_or(&self.rules.cities[0])
+    }
+
+    // Advances the game by some days, moving every market on each day and triggering
+    // random events on arrival
+    fn next_day(&mut self, days: i32, events: &mut Vec<GameEvent>) {
+        self.offer = None;
+        self.pass_days(days, events);
+        // Random events on arrival, rolled in the order that they were registered. The registry
+        // is lent out while they run, as events need the whole game to work on.
+        let registry = std::mem::take(&mut self.random_events);
+        registry.roll(self, events);
+        self.random_events = registry;
+    }
+
+    // Moves the calendar on by some days: interest on the debt and in the bank, and every
+    // market moves on each day
+    fn pass_days(&mut self, days: i32, events: &mut Vec<GameEvent>) {
+        for _ in 0..days {
+            self.settle_credit();
+            self.player.day += 1;
+            // Apply daily loan interest to the player's debt
+            if self.player.debt > 0 {
+                let rate = self.rules.lender_interest(self.player.lender);
+                let interest = (self.player.debt as f32 * rate).ceil() as i32;
+                self.player.debt += interest;
+                events.push(GameEvent::InterestApplied {
+                    interest,
+                    rate,
+                    debt: self.player.debt,
+                });
+            }
+            // Pay daily interest on the money in the bank
+            let interest = (self.player.bank as f32 * self.rules.bank_interest).floor() as i32;
+            if interest > 0 {
+                self.player.bank += interest;
+                events.push(GameEvent::BankInterest {
+                    interest,
+                    rate: self.rules.bank_interest,
+                    balance: self.player.bank,
+                });
+            }
+            self.pay_henchmen(events);
+            // Every city's market moves on, in map order so that games stay reproducible
+            for city in &self.rules.cities {
+                if let Some(market) = self.markets.get_mut(&city.name) {
+                    market.next_day(&self.rules, city, &mut self.rng);
+                }
+            }
+        }
+    }
+
+    // Pays each henchman's daily wage out of the cash on hand. Those who go unpaid may walk off;
+    // those who stay are patched up overnight.
+    fn pay_henchmen(&mut self, events: &mut Vec<GameEvent>) {
+        let hired = self.player.henchmen.len() as i32;
+        if hired == 0 {
+            return;
+        }
+        let terms = &self.rules.henchmen;
+        let paid = if terms.wage > 0 {
+            (self.player.cash / terms.wage).min(hired)
+        } else {
+            hired
+        };
+        if terms.wage > 0 && paid > 0 {
+            self.player.cash -= paid * terms.wage;
+            events.push(GameEvent::WagesPaid {
+                henchmen: paid,
+                wages: paid * terms.wage,
+            });
+        }
+        if paid < hired {
+            let deserted = (paid..hired)
+                .filter(|_| self.rng.gen_bool(terms.desert))
+                .count() as i32;
+            self.player.henchmen.truncate((hired - deserted) as usize);
+            events.push(GameEvent::WagesUnpaid {
+                unpaid: hired - paid,
+                deserted,
+                henchmen: hired - deserted,
+            });
+        }
+        for health in &mut self.player.henchmen {
+            *health = terms.health;
+        }
+    }
+
+    // Starts a fight. A player with nothing to fight with (and nobody to fight for them) can
+    // still run, hide or try one of the other ways out.
+    pub(crate) fn fight_event(&mut self, enemy: Enemy, events: &mut Vec<GameEvent>) {
+        // Every run-in with the cops makes them keener to catch the player
+        if enemy == Enemy::Cops {
+            self.player.heat += 1;
+        }
+        events.push(GameEvent::FightStarted {
+            enemy,
+            count: enemy.count(),
+        });
+        self.encounter = Some(Encounter {
+            enemy,
+            enemy_health: enemy.health(),
+            enemy_count: enemy.count(),
+            round: 0,
+        });
+        self.start_round(events);
+    }
+
+    // Opens the next round of the fight in progress
+    fn start_round(&mut self, events: &mut Vec<GameEvent>) {
+        let Some(encounter) = self.encounter.as_mut() else {
+            return;
+        };
+        encounter.round += 1;
+        // Funny line
+        let taunt = if self.rng.gen_bool(self.rules.events.taunt) {
+            encounter
+                .enemy
+                .taunts()
+                .choose(&mut self.rng)
+                .map(|line| line.to_string())
+        } else {
+            None
+        };
+        events.push(GameEvent::RoundStarted {
+            round: encounter.round,
+            taunt,
+            player_health: self.player.health,
+            enemy: encounter.enemy,
+            enemy_health: encounter.enemy_health,
+        });
+    }
+
+    // The player attacks and the enemy hits back
+    fn fight(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let Some(encounter) = self.encounter.as_mut() else {
+            return Ok(());
+        };
+        let enemy = encounter.enemy;
+        let weapon = self.player.best_weapon(&self.rules);
+        if weapon.is_none() && self.player.henchmen.is_empty() {
+            return Err(RuleError::Unarmed);
+        }
+        if let Some((slot, weapon)) = weapon {
+            // Player attacks with their best weapon, which may miss. Spare weapons make each hit
+            // a little harder.
+            let bonus = self.player.damage_bonus(&self.rules);
+            if self.rng.gen_bool(weapon.accuracy) {
+                let hit = self.rng.gen_range(weapon.min_damage..=weapon.max_damage);
+                let hit = (hit as f64 * bonus).round() as i32;
+                events.push(GameEvent::PlayerHit {
+                    weapon: weapon.name.clone(),
+                    damage: hit,
+                });
+                encounter.enemy_health -= hit;
+            } else {
+                events.push(GameEvent::PlayerMissed {
+                    weapon: weapon.name.clone(),
+                });
+            }
+            // Firearms use up ammunition and melee weapons wear out, hit or miss
+            if weapon.ammo > 0 {
+                self.player.ammo -= weapon.ammo;
+                if self.player.ammo < weapon.ammo {
+                    events.push(GameEvent::OutOfAmmo {
+                        weapon: weapon.name.clone(),
+                    });
+                }
+            }
+            self.player.weapons[slot].wear += 1;
+            if self.player.weapons[slot].attacks_left(&self.rules) == Some(0) {
+                self.player.weapons.remove(slot);
+                events.push(GameEvent::WeaponBroke {
+                    weapon: weapon.name.clone(),
+                });
+            }
+        }
+        // Then each henchman has a go
+        let terms = &self.rules.henchmen;
+        for _ in 0..self.player.henchmen.len() {
+            if self.rng.gen_bool(terms.accuracy) {
+                let hit = self.rng.gen_range(terms.min_damage..=terms.max_damage);
+                events.push(GameEvent::HenchmanHit { damage: hit });
+                encounter.enemy_health -= hit;
+            } else {
+                events.push(GameEvent::HenchmanMissed);
+            }
+        }
+        if encounter.enemy_health <= 0 {
+            let reward = self.rng.gen_range(1000..=5000) * encounter.enemy_count;
+            self.player.cash += reward;
+            if enemy == Enemy::Cops {
+                self.player.heat += 2;
+            }
+            events.push(GameEvent::EnemyDefeated {
+                enemy,
+                loot: reward,
+            });
+            self.encounter = None;
+            return Ok(());
+        }
+        // Enemy attacks
+        // let dmg = self.rng.gen_range(1..=5) * enemy_count; //seems a bit too much damage!
+        let dmg = if self.player.henchmen.is_empty() {
+            self.rng.gen_range(1..=2) * encounter.enemy_count
+        } else {
+            // With a crew around, each of them goes for the player or one of the henchmen
+            let mut dmg = 0;
+            for _ in 0..encounter.enemy_count {
+                let hit = self.rng.gen_range(1..=2);
+                let target = self.rng.gen_range(0..=self.player.henchmen.len());
+                if target == 0 {
+                    dmg += hit;
+                    continue;
+                }
+                let health = &mut self.player.henchmen[target - 1];
+                *health -= hit;
+                if *health > 0 {
+                    events.push(GameEvent::HenchmanWounded {
+                        enemy,
+                        damage: hit,
+                        health: *health,
+                    });
+                } else {
+                    self.player.henchmen.remove(target - 1);
+                    events.push(GameEvent::HenchmanKilled {
+                        enemy,
+                        henchmen: self.player.henchmen.len() as i32,
+                    });
+                }
+            }
+            dmg
+        };
+        if dmg > 0 {
+            let absorbed = self.take_damage(dmg);
+            events.push(GameEvent::EnemyHit {
+                enemy,
+                damage: dmg - absorbed,
+                absorbed,
+            });
+            self.wear_out_armor(events);
+        }
+        if self.player.health <= 0 {
+            self.defeated(enemy, events);
+            return Ok(());
+        }
+        self.start_round(events);
+        Ok(())
+    }
+
+    // The player tries to get away from the fight
+    fn run(&mut self, events: &mut Vec<GameEvent>) {
+        if self.rng.gen_bool(self.rules.events.escape) {
+            events.push(GameEvent::Escaped);
+            self.encounter = None;
+            return;
+        }
+        self.free_hit(
+            |damage, absorbed| GameEvent::EscapeFailed { damage, absorbed },
+            events,
+        );
+    }
+
+    // The player tries to buy the cops off. They only want it if they take it.
+    fn bribe(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let Some(encounter) = self.encounter.as_ref().filter(|e| e.enemy == Enemy::Cops) else {
+            return Err(RuleError::NoBribe);
+        };
+        let price = self
+            .rules
+            .bribe_price(encounter.enemy_count, self.player.heat);
+        if price > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        if self.rng.gen_bool(self.rules.events.bribe) {
+            self.player.cash -= price;
+            // Cops who have been paid once come back for more
+            self.player.heat += 1;
+            events.push(GameEvent::Bribed { cash: price });
+            self.encounter = None;
+        } else {
+            self.free_hit(
+                |damage, absorbed| GameEvent::BribeRefused { damage, absorbed },
+                events,
+            );
+        }
+        Ok(())
+    }
+
+    // The player drops a share of every drug carried and hopes that rival dealers go after
+    // it rather than them. The drugs are gone either way.
+    fn drop_drugs(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if self
+            .encounter
+            .as_ref()
+            .is_none_or(|encounter| encounter.enemy != Enemy::RivalDealers)
+        {
+            return Err(RuleError::NoDistraction);
+        }
+        let mut dropped = Vec::new();
+        for drug in self.rules.drug_list() {
+            if let Some(qty) = self.player.inventory.get_mut(&drug)
+                && *qty > 0
+            {
+                let share = ((*qty as f32 * self.rules.drop_share).ceil() as i32).min(*qty);
+                dropped.push((drug, share));
+                *qty -= share;
+            }
+        }
+        if dropped.is_empty() {
+            return Err(RuleError::NothingToDrop);
+        }
+        events.push(GameEvent::DrugsDropped { dropped });
+        if self.rng.gen_bool(self.rules.events.distract) {
+            events.push(GameEvent::Distracted);
+            self.encounter = None;
+        } else {
+            self.free_hit(
+                |damage, absorbed| GameEvent::DistractionFailed { damage, absorbed },
+                events,
+            );
+        }
+        Ok(())
+    }
+
+    // The player tries to lie low, which works better in some cities than others
+    fn hide(&mut self, events: &mut Vec<GameEvent>) {
+        if self.rng.gen_bool(self.rules.hide_chance(self.player.city)) {
+            events.push(GameEvent::Hidden);
+            self.encounter = None;
+            return;
+        }
+        self.free_hit(
+            |damage, absorbed| GameEvent::HideFailed { damage, absorbed },
+            events,
+        );
+    }
+
+    // The enemy gets a hit in when the player's way out of a fight doesn't work. `failure`
+    // reports it from the damage taken and the damage absorbed. The fight goes on if the
+    // player is still standing.
+    fn free_hit(
+        &mut self,
+        failure: impl FnOnce(i32, i32) -> GameEvent,
+        events: &mut Vec<GameEvent>,
+    ) {
+        // let dmg = self.rng.gen_range(2..=6); // seems like a too much damage!
+        let dmg = self.rng.gen_range(1..=3);
+        let absorbed = self.take_damage(dmg);
+        events.push(failure(dmg - absorbed, absorbed));
+        self.wear_out_armor(events);
+        if self.player.health <= 0 {
+            if let Some(enemy) = self.encounter.as_ref().map(|encounter| encounter.enemy) {
+                self.defeated(enemy, events);
+            }
+            return;
+        }
+        self.start_round(events);
+    }
+
+    // The player takes a hit. Any armor worn soaks up its share of the damage, for as long as
+    // it holds together, and the rest comes off their health. Returns the damage absorbed.
+    fn take_damage(&mut self, damage: i32) -> i32 {
+        let absorbed = match &mut self.player.armor {
+            Some(armor) => {
+                let share = self.rules.armor(armor.kind).map_or(0.0, |info| info.absorb);
+                let absorbed = ((damage as f64 * share).round() as i32)
+                    .min(armor.absorb_left(&self.rules))
+                    .max(0);
+                armor.wear += absorbed;
+                absorbed
+            }
+            None => 0,
+        };
+        self.player.health -= damage - absorbed;
+        absorbed
+    }
+
+    // Throws away armor that has soaked up all that it can
+    fn wear_out_armor(&mut self, events: &mut Vec<GameEvent>) {
+        if let Some(armor) = self.player.armor
+            && armor.absorb_left(&self.rules) <= 0
+        {
+            self.player.armor = None;
+            if let Some(info) = self.rules.armor(armor.kind) {
+                events.push(GameEvent::ArmorBroke {
+                    armor: info.name.clone(),
+                });
+            }
+        }
+    }
+
+    // The player goes down in a fight: the cops drag them off to jail, anyone else finishes
+    // them off
+    fn defeated(&mut self, enemy: Enemy, events: &mut Vec<GameEvent>) {
+        self.encounter = None;
+        if enemy == Enemy::Cops {
+            // Patched up just enough to stand trial
+            self.player.health = 1;
+            self.arrest(true, events);
+        } else {
+            events.push(GameEvent::PlayerDefeated { enemy });
+        }
+    }
+
+    // The player gives up to the cops
+    fn surrender(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if self
+            .encounter
+            .as_ref()
+            .is_none_or(|encounter| encounter.enemy != Enemy::Cops)
+        {
+            return Err(RuleError::NoSurrender);
+        }
+        self.encounter = None;
+        self.arrest(false, events);
+        Ok(())
+    }
+
+    // An arrest: the cops confiscate every drug in the trench coat and a share of the cash on
+    // hand, and the player sits out some days in jail (with the debt still growing)
+    fn arrest(&mut self, resisted: bool, events: &mut Vec<GameEvent>) {
+        let cash = (self.player.cash as f32 * self.rules.confiscation).floor() as i32;
+        self.player.cash -= cash;
+        // With the player locked up, the cops have other fish to fry
+        self.player.heat = 0;
+        let mut seized = Vec::new();
+        for drug in self.rules.drug_list() {
+            if let Some(qty) = self.player.inventory.get_mut(&drug)
+                && *qty > 0
+            {
+                seized.push((drug, *qty));
+                *qty = 0;
+            }
+        }
+        let days = if resisted {
+            self.rules.jail_days * 2
+        } else {
+            self.rules.jail_days
+        };
+        events.push(GameEvent::Arrested {
+            resisted,
+            days,
+            cash,
+            seized,
+        });
+        self.pass_days(days, events);
+        if !self.is_game_over() {
+            events.push(GameEvent::Released {
+                day: self.player.day,
+            });
+        }
+    }
+
+    // Handles player travel to a neighbouring city, paying for the trip and advancing the days
+    fn travel(&mut self, city: City, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let route = *self.here().route_to(city).ok_or(RuleError::NoRoute)?;
+        if route.cost > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= route.cost;
+        self.player.city = city;
+        events.push(GameEvent::Travelled {
+            city,
+            day: self.player.day + route.days,
+            cost: route.cost,
+        });
+        self.next_day(route.days, events);
+        Ok(())
+    }
+
+    // Opens the black market with fresh prices for trench coats, every weapon, medicine,
+    // ammunition and every piece of armor
+    fn shop_around(&mut self, events: &mut Vec<GameEvent>) {
+        let swing = 1.0 - SHOP_PRICE_SWING..=1.0 + SHOP_PRICE_SWING;
+        let coat_price = self.rng.gen_range(COAT_PRICES);
+        let weapon_prices = self
+            .rules
+            .weapons
+            .iter()
+            .map(|weapon| (weapon.price as f64 * self.rng.gen_range(swing.clone())).round() as i32)
+            .collect();
+        let med_price = self.rng.gen_range(MEDICINE_PRICES);
+        let ammo_price = self.rng.gen_range(AMMO_PRICES);
+        let armor_prices = self
+            .rules
+            .armor
+            .iter()
+            .map(|armor| (armor.price as f64 * self.rng.gen_range(swing.clone())).round() as i32)
+            .collect();
+        let offer = ShopOffer {
+            coat_price,
+            weapon_prices,
+            med_price,
+            ammo_price,
+            armor_prices,
+        };
+        self.shop = Some(offer.clone());
+        events.push(GameEvent::ShopOpened(offer));
+    }
+
+    // Buys one item from the black market, which closes it
+    fn shop_buy(&mut self, item: ShopItem, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let offer = self.shop.as_ref().ok_or(RuleError::NoShop)?;
+        let price = offer.price(item).ok_or(RuleError::UnknownWeapon)?;
+        if item == ShopItem::Medicine && self.player.health >= self.rules.max_health {
+            return Err(RuleError::HealthFull);
+        }
+        if item == ShopItem::Ammo {
+            let rounds = self.player.ammo + self.rules.ammo_box;
+            let space = self.rules.ammo_space(rounds) - self.rules.ammo_space(self.player.ammo);
+            if space > self.player.free_space(&self.rules) {
+                return Err(RuleError::NotEnoughSpace);
+            }
+        }
+        if self.player.cash < price {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= price;
+        self.give_item(item, events);
+        self.shop = None;
+        Ok(())
+    }
+
+    // Buys what a stranger is offering
+    fn accept_offer(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let offer = self.offer.ok_or(RuleError::NoOffer)?;
+        if self.player.cash < offer.price {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= offer.price;
+        self.give_item(offer.item, events);
+        self.offer = None;
+        Ok(())
+    }
+
+    // Hands the player something that they have just bought
+    fn give_item(&mut self, item: ShopItem, events: &mut Vec<GameEvent>) {
+        match item {
+            ShopItem::TrenchCoat => {
+                self.player.trenchcoat_space += 50;
+                events.push(GameEvent::CoatBought {
+                    space: self.player.trenchcoat_space,
+                });
+            }
+            ShopItem::Weapon(weapon) => {
+                let Some(info) = self.rules.weapon(weapon) else {
+                    return;
+                };
+                self.player.weapons.push(Weapon::new(weapon));
+                events.push(GameEvent::WeaponBought {
+                    weapon: info.name.clone(),
+                    weapons: self.player.weapons.len() as i32,
+                });
+            }
+            ShopItem::Ammo => {
+                self.player.ammo += self.rules.ammo_box;
+                events.push(GameEvent::AmmoBought {
+                    rounds: self.player.ammo,
+                });
+            }
+            // New armor replaces whatever was worn before
+            ShopItem::Armor(armor) => {
+                let Some(info) = self.rules.armor(armor) else {
+                    return;
+                };
+                self.player.armor = Some(Armor::new(armor));
+                events.push(GameEvent::ArmorBought {
+                    armor: info.name.clone(),
+                });
+            }
+            ShopItem::Medicine => {
+                self.player.health = self.rules.max_health;
+                events.push(GameEvent::MedicineBought {
+                    health: self.player.health,
+                });
+            }
+        }
+    }
+
+    // Pays off some of the loan shark debt
+    fn loan_shark(&mut self, amount: i32, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if amount <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if amount > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        if amount > self.player.debt {
+            return Err(RuleError::OverPayment);
+        }
+        // A big enough payment keeps the lender happy for a while longer
+        if amount as f32 >= self.player.debt as f32 * self.rules.min_payment
+            || amount == self.player.debt
+        {
+            self.player.last_paid = self.player.day;
+            self.player.enforcement = 0;
+        }
+        // Deduct payment from cash and debt
+        self.player.cash -= amount;
+        self.player.debt -= amount;
+        self.player.paid_today = self.player.paid_today.saturating_add(amount);
+        events.push(GameEvent::DebtPaid {
+            amount,
+            remaining: self.player.debt,
+        });
+        Ok(())
+    }
+
+    // Borrows more money, from the lender that the debt is owed to (or anyone, debt-free)
+    fn borrow(
+        &mut self,
+        lender: usize,
+        amount: i32,
+        events: &mut Vec<GameEvent>,
+    ) -> Result<(), RuleError> {
+        let Some(info) = self.rules.lender(lender) else {
+            return Err(RuleError::UnknownLender);
+        };
+        if amount <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if self.player.debt > 0 && lender != self.player.lender {
+            return Err(RuleError::OtherLender);
+        }
+        if amount > self.available_credit() {
+            return Err(RuleError::OverCreditLimit);
+        }
+        let name = info.name.clone();
+        // A fresh loan comes with a fresh deadline
+        if self.player.debt == 0 {
+            self.player.last_paid = self.player.day;
+        }
+        self.player.cash += amount;
+        self.player.debt += amount;
+        self.player.lender = lender;
+        self.player.borrowed_today = self.player.borrowed_today.saturating_add(amount);
+        events.push(GameEvent::Borrowed {
+            lender: name,
+            amount,
+            debt: self.player.debt,
+        });
+        Ok(())
+    }
+
+    // At the end of the day, raises the credit limit by however much more was paid back than
+    // borrowed (paying back money borrowed the same day proves nothing to the lender)
+    fn settle_credit(&mut self) {
+        let earned = (self.player.paid_today - self.player.borrowed_today).max(0);
+        self.player.repaid = self.player.repaid.saturating_add(earned);
+        self.player.borrowed_today = 0;
+        self.player.paid_today = 0;
+    }
+
+    // How much more the player can borrow before reaching the credit limit
+    pub fn available_credit(&self) -> i32 {
+        (self.player.credit_limit(&self.rules) - self.player.debt).max(0)
+    }
+
+    // What the debt will have grown to in some days' time if nothing is paid back
+    pub fn projected_debt(&self, days: i32) -> i32 {
+        let rate = self.rules.lender_interest(self.player.lender);
+        let mut debt = self.player.debt;
+        for _ in 0..days {
+            debt = debt.saturating_add((debt as f32 * rate).ceil() as i32);
+        }
+        debt
+    }
+
+    // Whether the player is in the city with the bank
+    pub fn at_bank(&self) -> bool {
+        self.player.city == self.rules.bank_city()
+    }
+
+    // Handles putting cash in the bank
+    fn deposit(&mut self, amount: i32, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if !self.at_bank() {
+            return Err(RuleError::NoBank);
+        }
+        if amount <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if amount > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= amount;
+        self.player.bank += amount;
+        events.push(GameEvent::Deposited {
+            amount,
+            balance: self.player.bank,
+        });
+        Ok(())
+    }
+
+    // Handles taking cash out of the bank
+    fn withdraw(&mut self, amount: i32, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if !self.at_bank() {
+            return Err(RuleError::NoBank);
+        }
+        if amount <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if amount > self.player.bank {
+            return Err(RuleError::NotEnoughSaved);
+        }
+        self.player.bank -= amount;
+        self.player.cash += amount;
+        events.push(GameEvent::Withdrawn {
+            amount,
+            balance: self.player.bank,
+        });
+        Ok(())
+    }
+
+    // Whether the player is in the city with their stash house
+    pub fn at_stash(&self) -> bool {
+        self.player.city == self.rules.stash_city()
+    }
+
+    // Handles leaving drugs at the stash house
+    fn stash(
+        &mut self,
+        drug: Drug,
+        qty: i32,
+        events: &mut Vec<GameEvent>,
+    ) -> Result<(), RuleError> {
+        if !self.at_stash() {
+            return Err(RuleError::NoStash);
+        }
+        let Some(info) = self.rules.drug_info(drug) else {
+            return Err(RuleError::UnknownDrug);
+        };
+        if qty <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if self.player.inventory.get(&drug).copied().unwrap_or(0) < qty {
+            return Err(RuleError::NotEnoughDrugs);
+        }
+        let free = self.player.stash_space(&self.rules) - self.player.stash_used(&self.rules);
+        if qty * info.space > free {
+            return Err(RuleError::StashFull);
+        }
+        *self.player.inventory.entry(drug).or_insert(0) -= qty;
+        let stashed = self.player.stash.entry(drug).or_insert(0);
+        *stashed += qty;
+        events.push(GameEvent::Stashed {
+            drug,
+            qty,
+            stashed: *stashed,
+        });
+        Ok(())
+    }
+
+    // Handles taking drugs from the stash house
+    fn unstash(
+        &mut self,
+        drug: Drug,
+        qty: i32,
+        events: &mut Vec<GameEvent>,
+    ) -> Result<(), RuleError> {
+        if !self.at_stash() {
+            return Err(RuleError::NoStash);
+        }
+        let Some(info) = self.rules.drug_info(drug) else {
+            return Err(RuleError::UnknownDrug);
+        };
+        if qty <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        let stashed = self.player.stash.get(&drug).copied().unwrap_or(0);
+        if stashed < qty {
+            return Err(RuleError::NotEnoughStashed);
+        }
+        if qty * info.space > self.player.free_space(&self.rules) {
+            return Err(RuleError::NotEnoughSpace);
+        }
+        *self.player.inventory.entry(drug).or_insert(0) += qty;
+        if stashed == qty {
+            self.player.stash.remove(&drug);
+        } else {
+            self.player.stash.insert(drug, stashed - qty);
+        }
+        events.push(GameEvent::Unstashed {
+            drug,
+            qty,
+            stashed: stashed - qty,
+        });
+        Ok(())
+    }
+
+    // Handles making the stash house bigger
+    fn upgrade_stash(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        if !self.at_stash() {
+            return Err(RuleError::NoStash);
+        }
+        let cost = self.rules.stash_upgrade_cost;
+        if cost > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= cost;
+        self.player.stash_upgrades += 1;
+        events.push(GameEvent::StashUpgraded {
+            space: self.player.stash_space(&self.rules),
+            cost,
+        });
+        Ok(())
+    }
+
+    // Handles taking on a henchman, who joins the player's fights from now on
+    fn hire_henchman(&mut self, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let terms = &self.rules.henchmen;
+        if self.player.henchmen.len() as i32 >= terms.max {
+            return Err(RuleError::CrewFull);
+        }
+        if terms.fee > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        self.player.cash -= terms.fee;
+        self.player.henchmen.push(terms.health);
+        events.push(GameEvent::HenchmanHired {
+            fee: terms.fee,
+            henchmen: self.player.henchmen.len() as i32,
+        });
+        Ok(())
+    }
+
+    // Handles buying drugs from the market
+    fn buy(&mut self, drug: Drug, qty: i32, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let Some(info) = self.rules.drug_info(drug) else {
+            return Err(RuleError::UnknownDrug);
+        };
+        if !self.market().is_available(drug) {
+            return Err(RuleError::SoldOut);
+        }
+        if qty <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        let listed = self.market().exact_price(drug).ok_or(RuleError::SoldOut)?;
+        let price = info.buy_cost(listed, qty);
+        if price > self.player.cash {
+            return Err(RuleError::NotEnoughCash);
+        }
+        if qty.saturating_mul(info.space) > self.player.free_space(&self.rules) {
+            return Err(RuleError::NotEnoughSpace);
+        }
+        // Complete the purchase, which pushes the local price up
+        self.player.cash -= price;
+        *self.player.inventory.entry(drug).or_insert(0) += qty;
+        self.markets
+            .get_mut(&self.player.city)
+            .unwrap()
+            .trade(info, qty);
+        events.push(GameEvent::Bought {
+            drug,
+            qty,
+            cost: price,
+        });
+        Ok(())
+    }
+
+    // Handles selling drugs from the player's inventory
+    fn sell(&mut self, drug: Drug, qty: i32, events: &mut Vec<GameEvent>) -> Result<(), RuleError> {
+        let Some(info) = self.rules.drug_info(drug) else {
+            return Err(RuleError::UnknownDrug);
+        };
+        if !self.market().is_available(drug) {
+            return Err(RuleError::SoldOut);
+        }
+        if qty <= 0 {
+            return Err(RuleError::InvalidQuantity);
+        }
+        if qty > self.player.inventory.get(&drug).copied().unwrap_or(0) {
+            return Err(RuleError::NotEnoughDrugs);
+        }
+        // Complete the sale, which pushes the local price down
+        let listed = self.market().exact_price(drug).ok_or(RuleError::SoldOut)?;
+        let price = info.sell_revenue(listed, qty);
+        self.player.cash += price;
+        *self.player.inventory.entry(drug).or_insert(0) -= qty;
+        self.markets
+            .get_mut(&self.player.city)
+            .unwrap()
+            .trade(info, -qty);
+        events.push(GameEvent::Sold {
+            drug,
+            qty,
+            revenue: price,
+        });
+        Ok(())
+    }
+
+    // Checks if the game is over due to days or health
+    pub fn is_game_over(&self) -> bool {
+        self.player.day > self.rules.start_days || self.player.health <= 0
+    }
+
+    // The final score out of 100, based on cash (including the bank)
+    pub fn final_score(&self) -> i32 {
+        let score = ((self.player.total_cash() as f32) / self.rules.score_divisor).min(100.0);
+        score.round() as i32
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Everything that a refused action must leave alone
+    type Snapshot = (
+        Player,
+        HashMap<City, Market>,
+        Option<Encounter>,
+        Option<ShopOffer>,
+        Option<StrangerOffer>,
+        u128,
+    );
+
+    fn snapshot(game: &Game) -> Snapshot {
+        (
+            game.player.clone(),
+            game.markets.clone(),
+            game.encounter.clone(),
+            game.shop.clone(),
+            game.offer,
+            game.rng.get_word_pos(),
+        )
+    }
+
+    #[test]
+    fn refused_actions_leave_the_game_untouched() {
+        let mut game = Game::with_seed(11);
+        let here = game.player.city;
+        let weed = Drug::named("Weed");
+        let refused = [
+            Action::Buy { drug: weed, qty: 0 },
+            Action::Buy {
+                drug: weed,
+                qty: i32::MAX,
+            },
+            Action::Sell { drug: weed, qty: 1 },
+            Action::Travel(here),
+            Action::PayDebt(i32::MAX),
+            Action::Fight,
+            Action::Run,
+            Action::LeaveShop,
+            Action::AcceptOffer,
+            Action::DismissHenchman,
+        ];
+        for action in refused {
+            let before = snapshot(&game);
+            assert!(game.apply(action).is_err(), "{:?} was allowed", action);
+            assert_eq!(snapshot(&game), before, "{:?} changed the game", action);
+        }
+
+        // In the middle of a fight only the ways out of it are allowed
+        game.encounter = Some(Encounter {
+            enemy: Enemy::RivalDealers,
+            enemy_health: 10,
+            enemy_count: 2,
+            round: 1,
+        });
+        for action in [
+            Action::Buy { drug: weed, qty: 1 },
+            Action::Surrender,
+            Action::Bribe,
+        ] {
+            let before = snapshot(&game);
+            assert!(game.apply(action).is_err(), "{:?} was allowed", action);
+            assert_eq!(snapshot(&game), before, "{:?} changed the game", action);
+        }
+    }
+
+    #[test]
+    fn credit_is_only_earned_by_paying_back_more_than_was_borrowed() {
+        let rules = Rules {
+            start_cash: 100_000,
+            ..Rules::default()
+        };
+        let loan = rules.loan_amount;
+        let mut game = Game::with_rules(5, rules.clone()).unwrap();
+        // Shuffling the same money back and forth earns nothing
+        for _ in 0..3 {
+            game.apply(Action::PayDebt(loan)).unwrap();
+            game.apply(Action::Borrow {
+                lender: 0,
+                amount: loan,
+            })
+            .unwrap();
+        }
+        let city = game.routes()[0].to;
+        game.apply(Action::Travel(city)).unwrap();
+        assert_eq!(game.player.credit_limit(&game.rules), loan);
+
+        // Paying it off for good raises the limit, from the next day on
+        let mut game = Game::with_rules(5, rules).unwrap();
+        game.apply(Action::PayDebt(loan)).unwrap();
+        assert_eq!(game.player.credit_limit(&game.rules), loan);
+        let city = game.routes()[0].to;
+        game.apply(Action::Travel(city)).unwrap();
+        assert_eq!(game.player.credit_limit(&game.rules), 2 * loan);
+    }
+
+    #[test]
+    fn splitting_a_trade_is_never_cheaper() {
+        let mut rules = Rules {
+            start_cash: 10_000_000,
+            start_space: 10_000,
+            ..Rules::default()
+        };
+        rules.events.sold_out = 0.0;
+        let weed = Drug::named("Weed");
+        let cash_after = |chunk: i32| {
+            let mut game = Game::with_rules(3, rules.clone()).unwrap();
+            let start = game.player.cash;
+            let mut left = 1000;
+            while left > 0 {
+                let qty = chunk.min(left);
+                game.apply(Action::Buy { drug: weed, qty }).unwrap();
+                left -= qty;
+            }
+            let spent = start - game.player.cash;
+            let before_selling = game.player.cash;
+            let mut left = 1000;
+            while left > 0 {
+                let qty = chunk.min(left);
+                game.apply(Action::Sell { drug: weed, qty }).unwrap();
+                left -= qty;
+            }
+            (spent, game.player.cash - before_selling)
+        };
+        let (bulk_cost, bulk_revenue) = cash_after(1000);
+        for chunk in [1, 3, 7, 10, 99, 250, 999] {
+            let (cost, revenue) = cash_after(chunk);
+            assert!(cost >= bulk_cost, "buying {} at a time cost less", chunk);
+            assert!(
+                revenue <= bulk_revenue,
+                "selling {} at a time earned more",
+                chunk
+            );
+        }
+    }
+}
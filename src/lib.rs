@@ -0,0 +1,45 @@
+// The Drugwars game engine.
+// All of the game rules live in this library and none of it touches the terminal. A front-end
+// (such as the interactive game in `main.rs`, a bot or a test) drives the game by handing
+// `Action`s to `Game::apply` and deciding for itself how to show the `GameEvent`s that come back.
+
+mod action; // Actions the player can take, the events they produce and the rule errors
+mod game; // The game state machine
+mod market; // Drugs, cities and price generation
+mod player; // The player and their belongings
+mod random_events; // The random events that can happen on arrival
+mod replay; // Recording games and playing them back
+mod rules; // The tunable rules of the game, read from rules.toml
+mod save; // Saving and loading games
+mod simulate; // Playing many games headlessly for balance analysis
+mod strategy; // Computer players
+
+pub use action::{
+    Action, Enemy, GameEvent, PriceChange, RuleError, ShopItem, ShopOffer, StrangerOffer,
+};
+pub use game::{Encounter, Game};
+pub use market::{City, CityInfo, Drug, DrugInfo, EventRisk, Market, PriceModel, Route};
+pub use player::{Armor, Player, Weapon};
+pub use random_events::{EventRegistry, RandomEvent};
+pub use replay::{Divergence, Outcome, Recording, Replayer, Step};
+pub use rules::{
+    ArmorInfo, DEFAULT_RULES_FILE, EventOdds, HenchmanTerms, Lender, Rules, RulesError, WeaponInfo,
+};
+pub use save::SaveError;
+pub use simulate::{Distribution, Ending, GameSummary, SimulationReport, run_game, simulate};
+pub use strategy::{
+    DebtFirstStrategy, GreedyStrategy, Observation, RandomStrategy, STRATEGY_NAMES, Strategy,
+    choose_action, strategy_by_name,
+};
+
+// // // // // // // // // // // // // // // // //
+// Default starting values and rules
+// These are used wherever rules.toml does not say otherwise (see rules.rs)
+pub const START_CASH: i32 = 2000; // Player's starting cash
+pub const START_SPACE: i32 = 100; // Starting trench coat space (inventory limit)
+pub const START_DAYS: i32 = 30; // Number of days in the game
+pub const LOAN_INTEREST: f32 = 0.15; // Daily loan interest rate
+pub const LOAN_AMOUNT: i32 = 5000; // Initial loan amount
+pub const MAX_HEALTH: i32 = 10; // Maximum health of the player
+pub const START_WEAPONS: i32 = 0; // Starting number of weapons.
+// // // // // // // // // // // // // // // // // //
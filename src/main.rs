@@ -1,14 +1,15 @@
-// Note: I was going to put all these functions into separate files to make some organizational sense. I eventually decided to keep all code in one large `main.rs` file for the simplicity of tracking borrowed variables across functions. I may refactor all this code later once I have done more testing and added more features. Stay tuned!
+// The interactive terminal front-end for Drugwars.
+// All of the game rules live in the library (see `lib.rs`); this file only asks the player what
+// they want to do, turns the answer into an `Action` and prints the `GameEvent`s that come back.
 
-// Import necessary crates for randomness, collections, and input/output
+// Import necessary crates for colours and input/output
 // 'colored' is used for colored terminal output
-// 'rand' is used for random number generation (for prices, events, etc.)
-// 'HashMap' is used for inventory and price tables
 // 'io' and 'Write' are used for user input and flushing output
 use colored::Colorize;
-use rand::Rng;
-use rand::seq::SliceRandom;
-use std::collections::HashMap;
+use drugwars_rs::{
+    Action, DEFAULT_RULES_FILE, Drug, Enemy, Game, GameEvent, Outcome, PriceChange, Recording,
+    Route, RuleError, Rules, STRATEGY_NAMES, ShopItem, Weapon, run_game, strategy_by_name,
+};
 use std::io;
 use std::io::Write;
 
@@ -20,454 +21,606 @@ const COLOR_GREEN: &str = "\x1b[32m";
 const COLOR_RED: &str = "\x1b[31m";
 const COLOR_CYAN: &str = "\x1b[36m";
 const COLOR_MAGENTA: &str = "\x1b[35m";
+const COLOR_GREY: &str = "\x1b[90m";
 
-// // // // // // // // // // // // // // // // //
-// Game constants for starting values and rules
-// These can be tweaked for balancing or testing
-const START_CASH: i32 = 2000; // Player's starting cash
-    // const START_CASH: i32 = 2000000; // used for testing with more cash
-const START_SPACE: i32 = 100; // Starting trench coat space (inventory limit)
-const START_DAYS: i32 = 30; // Number of days in the game
-const LOAN_INTEREST: f32 = 0.15; // Daily loan interest rate
-const LOAN_AMOUNT: i32 = 5000; // Initial loan amount
-    // const LOAN_AMOUNT: i32 = 0; // used for testing the game without a loan
-const MAX_HEALTH: i32 = 10; // Maximum health of the player
-    // const MAX_HEALTH: i32 = 1; // Used for testing health of the player
-const START_WEAPONS: i32 = 0; // Starting number of weapons.
-    // const START_WEAPONS: i32 = 30; // Used for testing the game with weapons.
-// // // // // // // // // // // // // // // // // //
-
+mod batch; // The `simulate` subcommand for playing many games headlessly
 mod toml_extract; // Extract and print the version information according to the toml file
 
-// Enum representing all drug types in the game
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Drug {
-    Cocaine,
-    Heroin,
-    Acid,
-    Weed,
-    Speed,
-    Ludes,
-}
-
-impl Drug {
-    // For returning a vector of all drug variants
-    fn all() -> Vec<Drug> {
-        vec![
-            Drug::Cocaine,
-            Drug::Heroin,
-            Drug::Acid,
-            Drug::Weed,
-            Drug::Speed,
-            Drug::Ludes,
-        ]
-    }
-    // For returning the display name for each drug
-    fn name(&self) -> &'static str {
-        match self {
-            Drug::Cocaine => "Cocaine",
-            Drug::Heroin => "Heroin",
-            Drug::Acid => "Acid",
-            Drug::Weed => "Weed",
-            Drug::Speed => "Speed",
-            Drug::Ludes => "Ludes",
-        }
-    }
-}
-
-// Enum for representing all city locations in the game
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum City {
-    Manhattan,
-    Bronx,
-    Brooklyn,
-}
+const DEFAULT_SAVE_FILE: &str = "drugwars_save.toml"; // Where games are saved unless told otherwise
 
-impl City {
-    // For returning a vector of all city variants
-    fn all() -> Vec<City> {
-        vec![City::Manhattan, City::Bronx, City::Brooklyn]
-    }
-    // For returning the display name for each city
-    fn name(&self) -> &'static str {
-        match self {
-            City::Manhattan => "Manhattan",
-            City::Bronx => "The Bronx",
-            City::Brooklyn => "Brooklyn",
-        }
+// Prints a prompt and returns the trimmed line that the player types
+fn read_input(prompt: &str) -> String {
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    // Nothing left to read (e.g. the input was piped in): there is no one left to play
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        println!();
+        std::process::exit(0);
     }
+    input.trim().to_string()
 }
 
-// Struct for representing the player and their state
-struct Player {
-    cash: i32,                     // Player's current cash
-    debt: i32,                     // Player's current debt
-    health: i32,                   // Player's health (max 10)
-    trenchcoat_space: i32,         // Max inventory space
-    inventory: HashMap<Drug, i32>, // Drug inventory
-    weapons: i32,                  // Number of weapons owned
-    day: i32,                      // Current day
-    city: City,                    // Current city
+// The game being played, plus its recording when --record is on
+struct Session {
+    game: Game,
+    recording: Option<(Recording, String)>, // The recording and the file it is kept in
 }
 
-impl Player {
-    // For creating new player with initial values (defined above)
-    // Note these values can be changed to test the game
-    // or to make it easier to play!
-    // For example, you can set START_CASH to 2000000 to start with 2 million cash
-    // or set START_SPACE to 1000 to start with 1000 trenchcoat space
-    // or set MAX_HEALTH to 100 to start with 100 health
-    // or set START_DAYS to 100 to have 100 days to play
-    // or set LOAN_AMOUNT to 100000 to start with 100k debt
-    // or set LOAN_INTEREST to 0.05 to have 5% daily interest on the loan
-    // or set START_CASH to 1000000 and START_SPACE to 1000 to start with 1 million cash and 1000 space
-    // or set MAX_HEALTH to 100 and START_DAYS to 100 to have 100 health and 100 days to play
-    // or set START_WEAPONS to 10 so that each finger gets one!
-    // But you don't need that, right?!!
-
-    fn new() -> Self {
-        let mut inventory = HashMap::new();
-        for drug in Drug::all() {
-            inventory.insert(drug, 0);
+impl Session {
+    // Applies an action and prints whatever happened (or why it was refused)
+    fn play(&mut self, action: Action) {
+        let result = self.game.apply(action);
+        match &result {
+            Ok(events) => print_events(self.game.rules(), events),
+            Err(err) => print_rule_error(*err),
         }
-        Player {
-            cash: START_CASH,
-            debt: LOAN_AMOUNT,
-            health: MAX_HEALTH,
-            trenchcoat_space: START_SPACE,
-            inventory,
-            // weapons: 0, //used for debugging ... you never know when you might need it!
-            weapons: START_WEAPONS,
-            day: 1,
-            city: City::Manhattan,
+        // The replay file is rewritten after every action so that nothing is lost if the game is killed
+        if let Some((recording, path)) = &mut self.recording {
+            recording.record(action, &result);
+            if let Err(err) = recording.save(path) {
+                colour_print(&format!("\t {}", err), "red");
+            }
         }
     }
-    // For returning the total number of drugs carried
-    fn total_drugs(&self) -> i32 {
-        self.inventory.values().sum()
-    }
 }
 
-// Struct for representing the overall game state
-struct Game {
-    player: Player,             // The player
-    prices: HashMap<Drug, i32>, // Current drug prices
-    rng: rand::rngs::ThreadRng, // Random number generator
+// Prints a refused action in red
+fn print_rule_error(err: RuleError) {
+    println!(
+        "\t {RED}{}{RESET}",
+        err,
+        RED = COLOR_RED,
+        RESET = COLOR_RESET
+    );
 }
 
-impl Game {
-    // Creates a new game with a new player and initial prices
-    fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let prices = Game::generate_prices(&mut rng);
-        Game {
-            player: Player::new(),
-            prices,
-            rng,
-        }
-    }
-
-    // For generating random prices for each drug for the current day
-    fn generate_prices(rng: &mut rand::rngs::ThreadRng) -> HashMap<Drug, i32> {
-        let mut prices = HashMap::new();
-        for drug in Drug::all() {
-            let price = match drug {
-                Drug::Cocaine => rng.gen_range(1500..=30000),
-                Drug::Heroin => rng.gen_range(1000..=14000),
-                Drug::Acid => rng.gen_range(100..=1000),
-                Drug::Weed => rng.gen_range(90..=800),
-                Drug::Speed => rng.gen_range(100..=2500),
-                Drug::Ludes => rng.gen_range(10..=600),
-            };
-            prices.insert(drug, price);
-        }
-        prices
-    }
-
-    // Prints the player's current status and inventory
-    fn print_status(&self) {
+// Prints the player's current status and inventory
+fn print_status(game: &Game) {
+    println!(
+        "\n\t {CYAN}Day {}/{} in {}{RESET}",
+        game.player.day,
+        game.rules().start_days,
+        game.player.city.name(),
+        CYAN = COLOR_CYAN,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {YELLOW}Cash: ${}{RESET}",
+        game.player.cash,
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    if game.player.is_overdue(game.rules()) {
         println!(
-            "\n\t {CYAN}Day {}/{} in {}{RESET}",
-            self.player.day,
-            START_DAYS,
-            self.player.city.name(),
-            CYAN = COLOR_CYAN,
+            "\t {RED}Debt: ${} (overdue!){RESET}",
+            game.player.debt,
+            RED = COLOR_RED,
             RESET = COLOR_RESET
         );
+    } else if game.player.debt > 0 {
         println!(
-            "\t {YELLOW}Cash: ${}{RESET}",
-            self.player.cash,
+            "\t {YELLOW}Debt: ${} (payment due by day {}){RESET}",
+            game.player.debt,
+            game.player.due_day(game.rules()),
             YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET
         );
+    } else {
         println!(
-            "\t {YELLOW}Debt: ${}{RESET}",
-            self.player.debt,
+            "\t {YELLOW}Debt: $0{RESET}",
             YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET
         );
-        println!(
-            "\t {GREEN}Health: {}/{}{RESET}",
-            self.player.health,
-            MAX_HEALTH,
-            GREEN = COLOR_GREEN,
-            RESET = COLOR_RESET
-        );
-        println!(
-            "\t {MAGENTA}Weapons: {}{RESET}",
-            self.player.weapons,
-            MAGENTA = COLOR_MAGENTA,
-            RESET = COLOR_RESET
-        );
-        println!(
-            "\t Trenchcoat space: {}/{}",
-            self.player.total_drugs(),
-            self.player.trenchcoat_space
-        );
-        println!("\t Inventory:");
-        print_a_line(COLOR_GREEN);
-        for drug in Drug::all() {
-            let qty = self.player.inventory[&drug];
-            if qty > 0 {
-                println!(
-                    "\t {CYAN}█  {}: {}{RESET}",
-                    drug.name(),
-                    qty,
-                    CYAN = COLOR_CYAN,
-                    RESET = COLOR_RESET
-                );
-                print_a_line(COLOR_GREEN);
-            }
-        }
-        println!("\n\t Current prices:");
-        // Print a separator line
-        print_a_line(COLOR_YELLOW);
-        for drug in Drug::all() {
+    }
+    println!(
+        "\t {YELLOW}Bank: ${} (in {}){RESET}",
+        game.player.bank,
+        game.rules().bank_city().name(),
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {GREEN}Health: {}/{}{RESET}",
+        game.player.health,
+        game.rules().max_health,
+        GREEN = COLOR_GREEN,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {MAGENTA}Weapons: {}{RESET}",
+        arsenal(game),
+        MAGENTA = COLOR_MAGENTA,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {MAGENTA}Ammo: {} rounds{RESET}",
+        game.player.ammo,
+        MAGENTA = COLOR_MAGENTA,
+        RESET = COLOR_RESET
+    );
+    let armor = match game.player.armor {
+        Some(armor) => match game.rules().armor(armor.kind) {
+            Some(info) => format!(
+                "{} (soaks up {} more damage)",
+                info.name,
+                armor.absorb_left(game.rules())
+            ),
+            None => "none".to_string(),
+        },
+        None => "none".to_string(),
+    };
+    println!(
+        "\t {MAGENTA}Armor: {}{RESET}",
+        armor,
+        MAGENTA = COLOR_MAGENTA,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {MAGENTA}Henchmen: {} (${} a day){RESET}",
+        game.player.henchmen.len(),
+        game.player.henchmen.len() as i32 * game.rules().henchmen.wage,
+        MAGENTA = COLOR_MAGENTA,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t {MAGENTA}Heat: {}{RESET}",
+        game.player.heat,
+        MAGENTA = COLOR_MAGENTA,
+        RESET = COLOR_RESET
+    );
+    println!(
+        "\t Trenchcoat space: {}/{}",
+        game.player.space_used(game.rules()),
+        game.player.trenchcoat_space
+    );
+    println!(
+        "\t Stash house: {}/{} (in {})",
+        game.player.stash_used(game.rules()),
+        game.player.stash_space(game.rules()),
+        game.rules().stash_city().name()
+    );
+    println!("\t Inventory:");
+    print_a_line(COLOR_GREEN);
+    for drug in game.rules().drug_list() {
+        let qty = game.player.inventory.get(&drug).copied().unwrap_or(0);
+        if qty > 0 {
             println!(
-                "\t {CYAN}█   {YELLOW}{}: ${}{RESET}",
+                "\t {CYAN}█  {}: {}{RESET}",
                 drug.name(),
-                self.prices[&drug],
+                qty,
                 CYAN = COLOR_CYAN,
-                YELLOW = COLOR_YELLOW,
                 RESET = COLOR_RESET
             );
+            print_a_line(COLOR_GREEN);
         }
-        print_a_line(COLOR_YELLOW);
     }
-
-    // Advances the game by one day, updating prices and triggering random events
-    fn next_day(&mut self) {
-        self.player.day += 1;
-        self.prices = Game::generate_prices(&mut self.rng);
-        // Apply daily loan interest to the player's debt
-        if self.player.debt > 0 {
-            let interest = (self.player.debt as f32 * LOAN_INTEREST).ceil() as i32;
-            self.player.debt += interest;
+    println!("\n\t Today's prices in {}:", game.player.city.name());
+    // Print a separator line
+    print_a_line(COLOR_YELLOW);
+    for drug in game.rules().drug_list() {
+        if !game.market().is_available(drug) {
             println!(
-                "\t{RED}Loan shark interest applied: +${} ({}% daily). New debt: ${}{RESET}",
-                interest,
-                (LOAN_INTEREST * 100.0) as i32,
-                self.player.debt,
-                RED = COLOR_RED,
+                "\t {CYAN}█   {GREY}{}: none today{RESET}",
+                drug.name(),
+                CYAN = COLOR_CYAN,
+                GREY = COLOR_GREY,
                 RESET = COLOR_RESET
             );
+            continue;
         }
-        // Random news flashes that affect drug prices
-        self.random_news_flash();
-        // Random events: rival dealers or cops
-        self.random_fight_event();
-        // TODO: Add more random events, offers, etc.
+        println!(
+            "\t {CYAN}█   {YELLOW}{}: ${}{RESET}{}",
+            drug.name(),
+            game.prices()[&drug],
+            price_change(game.market().change(drug)),
+            CYAN = COLOR_CYAN,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        );
     }
+    print_a_line(COLOR_YELLOW);
+}
 
-    // Random news flashes that cause sudden price changes
-    fn random_news_flash(&mut self) {
-        use rand::Rng;
-        // 30% chance of a news flash each day
-        if self.rng.gen_bool(0.3) {
-            // Pick a random drug
-            let drug = *Drug::all().choose(&mut self.rng).unwrap();
-            // Pick a random event: 0 = price drop, 1 = price spike
-            let event = self.rng.gen_range(0..=1);
-            let news = match (drug, event) {
-                (Drug::Cocaine, 0) => "Colombian cartel goes on vacation. Cocaine prices plummet!",
-                (Drug::Cocaine, 1) => "Wall Street bonus season! Cocaine prices skyrocket!",
-                (Drug::Heroin, 0) => "Yoga craze sweeps the city. Heroin prices crash!",
-                (Drug::Heroin, 1) => "Hipsters discover 'vintage' heroin. Prices soar!",
-                (Drug::Acid, 0) => "Bad trip at Burning Man. Acid prices tank!",
-                (Drug::Acid, 1) => "Psychedelic parade! Acid prices go wild!",
-                (Drug::Weed, 0) => "Police raid local dispensary. Weed prices nosedive!",
-                (Drug::Weed, 1) => "The DrugCON meeting is held in NYC. Weed prices blaze up!",
-                (Drug::Speed, 0) => "Caffeine is back in style. Speed prices collapse!",
-                (Drug::Speed, 1) => "All-night coding hackathon! Speed prices explode!",
-                (Drug::Ludes, 0) => "Wolf of Wall Street arrested. Ludes prices drop!",
-                (Drug::Ludes, 1) => "Retro party! Ludes prices go through the roof!",
-                _ => "Strange news in the city! Prices are acting weird!",
-            };
-            // Apply the price change
-            let price = self.prices.get_mut(&drug).unwrap();
-            if event == 0 {
-                // Sudden drop: 40-70% off
-                let drop = self.rng.gen_range(40..=70);
-                *price = (*price as f32 * (1.0 - drop as f32 / 100.0)).max(1.0) as i32;
-                println!(
-                    "\t{CYAN}NEWS FLASH: {news}{RESET}",
-                    CYAN = COLOR_CYAN,
-                    news = news,
-                    RESET = COLOR_RESET
-                );
-                println!(
-                    "\t{YELLOW}>> {} price drops by {}%! Now: ${}{RESET}",
-                    drug.name(),
-                    drop,
-                    price,
-                    YELLOW = COLOR_YELLOW,
-                    RESET = COLOR_RESET
-                );
-            } else {
-                // Sudden rise: 50-120% up
-                let rise = self.rng.gen_range(50..=120);
-                *price = (*price as f32 * (1.0 + rise as f32 / 100.0)).max(1.0) as i32;
-                println!(
-                    "\t{CYAN}NEWS FLASH: {news}{RESET}",
-                    CYAN = COLOR_CYAN,
-                    news = news,
-                    RESET = COLOR_RESET
-                );
+// Prints the events returned by the game, in order
+fn print_events(rules: &Rules, events: &[GameEvent]) {
+    for event in events {
+        print_event(rules, event);
+    }
+}
+
+// Prints one game event the way the classic game did
+fn print_event(rules: &Rules, event: &GameEvent) {
+    match event {
+        GameEvent::Bought { drug, qty, cost } => println!(
+            "\t {GREEN}Bought {} {} for ${}.{RESET}",
+            qty,
+            drug.name(),
+            cost,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Sold { drug, qty, revenue } => println!(
+            "\t {GREEN}Sold {} {} for ${}.{RESET}",
+            qty,
+            drug.name(),
+            revenue,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Travelled { city, cost, .. } => {
+            if *cost > 0 {
                 println!(
-                    "\t{YELLOW}>> {} price rises by {}%! Now: ${}{RESET}",
-                    drug.name(),
-                    rise,
-                    price,
+                    "\t {YELLOW}The trip to {} cost you ${}.{RESET}",
+                    city.name(),
+                    cost,
                     YELLOW = COLOR_YELLOW,
                     RESET = COLOR_RESET
                 );
             }
         }
-    }
-
-    // Random fight event: rival drug dealers or cops
-    fn random_fight_event(&mut self) {
-        use rand::Rng;
-        // 20% chance of a fight event each day
-        // if self.rng.gen_bool(0.2) {
-        if self.rng.gen_bool(0.8) { //80% chance of a fight event each day
-            let is_cop = self.rng.gen_bool(0.5);
-            if is_cop {
+        GameEvent::InterestApplied {
+            interest,
+            rate,
+            debt,
+        } => println!(
+            "\t{RED}Loan shark interest applied: +${} ({}% daily). New debt: ${}{RESET}",
+            interest,
+            (rate * 100.0) as i32,
+            debt,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::DebtPaid { amount, remaining } => println!(
+            "\t {GREEN}You paid ${} to the loan shark. Remaining debt: ${}{RESET}",
+            amount,
+            remaining,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Borrowed {
+            lender,
+            amount,
+            debt,
+        } => println!(
+            "\t {YELLOW}{} hands you ${}. You now owe ${}.{RESET}",
+            lender,
+            amount,
+            debt,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::LoanWarning { lender, debt } => println!(
+            "\t {RED}A message from {}: 'You owe us ${}. Pay up, or we come to you.'{RESET}",
+            lender,
+            debt,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::DebtCollected {
+            lender,
+            amount,
+            debt,
+        } => println!(
+            "\t {RED}{}'s enforcers empty your pockets: ${} goes towards your debt. You still owe ${}.{RESET}",
+            lender,
+            amount,
+            debt,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::DrugsSeized { lender, drug, qty } => println!(
+            "\t {RED}{}'s enforcers take your {} {} for their trouble.{RESET}",
+            lender,
+            qty,
+            drug.name(),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::BonesBroken {
+            lender,
+            damage,
+            health,
+        } => println!(
+            "\t {RED}{}'s enforcers break a few of your bones as a reminder. -{} health (now {}).{RESET}",
+            lender,
+            damage,
+            health,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Stashed { drug, qty, stashed } => println!(
+            "\t {GREEN}You stashed {} {}. Now in the stash house: {}{RESET}",
+            qty,
+            drug.name(),
+            stashed,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Unstashed { drug, qty, stashed } => println!(
+            "\t {GREEN}You took {} {} from the stash house. Still stashed: {}{RESET}",
+            qty,
+            drug.name(),
+            stashed,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::StashUpgraded { space, cost } => println!(
+            "\t {GREEN}You paid ${} to expand your stash house. It now holds {}.{RESET}",
+            cost,
+            space,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanHired { fee, henchmen } => println!(
+            "\t {GREEN}You hire a henchman for ${}. Your crew is {} strong.{RESET}",
+            fee,
+            henchmen,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanDismissed { henchmen } => println!(
+            "\t {YELLOW}You let a henchman go. Your crew is {} strong.{RESET}",
+            henchmen,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::WagesPaid { henchmen, wages } => println!(
+            "\t {YELLOW}You pay your {} henchm{}n ${} in wages.{RESET}",
+            henchmen,
+            if *henchmen == 1 { "a" } else { "e" },
+            wages,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::WagesUnpaid {
+            unpaid,
+            deserted,
+            henchmen,
+        } => {
+            println!(
+                "\t {RED}You can't pay {} of your henchmen today!{RESET}",
+                unpaid,
+                RED = COLOR_RED,
+                RESET = COLOR_RESET
+            );
+            if *deserted > 0 {
                 println!(
-                    "\t{CYAN}NEWS FLASH: Officer Hardass and his deputies are on a donut break... but spot you!{RESET}",
-                    CYAN = COLOR_CYAN,
+                    "\t {RED}{} of them walk off. Your crew is {} strong.{RESET}",
+                    deserted,
+                    henchmen,
+                    RED = COLOR_RED,
                     RESET = COLOR_RESET
                 );
-                self.fight_event(
-                    "Cops",
-                    15,
-                    2,
-                    vec![
-                    //     "\t Officer Hardass yells: 'Freeze, scumbag!'",
-                    //     "\t A deputy drops his donut and draws his gun!",
-                    //     "\t The police radio blares: 'Suspect is armed and fabulous!'",
-                    "\t A deputy drops his donut and draws his gun, only to trip on the sidewalk and face-plant.",
-                    "\t A deputy drops his donut and draws his gun!",
-                    "\t A deputy gets his badge stuck in a tree: 'This is not how I envisioned my career.'",
-                    "\t A patrol car screeches to a halt: 'We have a situation... of epic proportions!'",
-                    "\t A police officer tries to intimidate the suspect by using a fake mustache, but ends up looking ridiculous instead.",
-                    "\t A rookie cop accidentally arrests a man who looks just like him, leading to an awkward exchange.",
-                    "\t An officer claims to have 'expertly' handcuffed the suspect, only for them to easily slip out of the cuffs.",
-                    "\t An officer gets stuck in the doorway of the suspect's car and has to be pulled out by two other officers.",
-                    "\t Officer Bob mistakes a bag of chips for a stash of drugs and starts searching it with a magnifying glass.",
-                    "\t Officer Hardass yells: 'Freeze, scumbag! But first, let me check my clipboard...'",
-                    "\t Officer Hardass yells: 'Freeze, scumbag!'",
-                    "\t Officer Johnson says: 'I've got you surrounded, suspect... on the other side of this building.'",
-                    "\t Officer Jones shouts: 'I'm not searching you, I'm just... um... admiring your vehicle!'",
-                    "\t Officer Smith barks into the mic: 'What's this? A warrant? No, no, no! I was just, uh, conducting research!'",
-                    "\t The police car gets stuck in the parking lot due to the officer's ineptitude at parallel parking.",
-                    "\t The police chief yells: 'Code 55: Code 55! That means we're out of donuts.'",
-                    "\t The police radio blares: 'Suspect is armed and fabulous! Can we also order a box of donuts?'",
-                    "\t The police radio blares: 'Suspect is armed and fabulous!'",
-                    "\t The police radio crackles: 'All units, we have a report of suspicious activity... like someone eating an entire pizza by themselves.'",
-                    "\t The police sirens are so loud that they shatter the suspect's sunglasses.",
-
-                    ],
-                );
-            } else {
+            }
+        }
+        GameEvent::StashRaided { city, seized } => {
+            println!(
+                "\t {RED}NEWS FLASH: Police raid a stash house in {}!{RESET}",
+                city.name(),
+                RED = COLOR_RED,
+                RESET = COLOR_RESET
+            );
+            for (drug, qty) in seized {
                 println!(
-                    "\t {CYAN}NEWS FLASH: Rival drug dealers challenge you to a turf war!{RESET}",
-                    CYAN = COLOR_CYAN,
+                    "\t {RED}The cops walk off with {} of your {}.{RESET}",
+                    qty,
+                    drug.name(),
+                    RED = COLOR_RED,
                     RESET = COLOR_RESET
                 );
-                self.fight_event(
-                    "Rival Dealers",
-                    10,
-                    1,
-                    vec![
-                        "\t A rival yells: 'This is our block now!'",
-                        "\t Someone throws a bag of oregano at you!",
-                        "\t A dealer shouts: 'You call that product?'",
-                        "\t Looks like someone's supply ran out... of dignity!",
-                        "\t I see you're still peddling the same old trash, dude.",
-                        "\t You must have misspelled ' failure' on your storefront sign!",
-                        "\t I heard your product is so bad, it needs its own hazmat suit!",
-                        "\t Looks like you left the competition to me... and my amazing deals!",
-                        "\t Your operation looks like a 3rd-grader's art project gone wrong",
-                        "\t Is that a 'Closed' sign or just a prayer?",
-                        "\t I'm starting a betting pool on how long it takes for you to get shut down.",
-                        "\t You know what they say: 'you can't buy happiness, but I heard they're selling it cheap at your store'",
-                        "\t It looks like someone's trying out for the role of ' failed entrepreneur'... nice try!",
-                        "\t Your reputation is so shot, I think it's still in rehab",
-                        "\t Looks like you took the phrase 'on the rocks' too literally",
-                        "\t I heard your product is so old, it's been known to be used as bookends",
-                        "\t You must have hired a team of experts... at losing",
-                        "\t This block? I think it's still on rent. You're just squatting",
-
-
-                    ],
-                );
             }
         }
-    }
-
-    // Fight event logic
-    fn fight_event(
-        &mut self,
-        enemy: &str,
-        mut enemy_health: i32,
-        enemy_count: i32,
-        funny_lines: Vec<&str>,
-    ) {
-        use rand::Rng;
-        if self.player.weapons == 0 {
+        GameEvent::Deposited { amount, balance } => println!(
+            "\t {GREEN}You deposited ${}. Bank balance: ${}{RESET}",
+            amount,
+            balance,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Withdrawn { amount, balance } => println!(
+            "\t {GREEN}You withdrew ${}. Bank balance: ${}{RESET}",
+            amount,
+            balance,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::BankInterest {
+            interest,
+            rate,
+            balance,
+        } => println!(
+            "\t{GREEN}Bank interest paid: +${} ({}% daily). New balance: ${}{RESET}",
+            interest,
+            rate * 100.0,
+            balance,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Mugged { amount, cash } => println!(
+            "\t{CYAN}NEWS FLASH: You get mugged on the subway! They take ${}, leaving you ${}.{RESET}",
+            amount,
+            cash,
+            CYAN = COLOR_CYAN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::FoundDrugs { drug, qty } => println!(
+            "\t{CYAN}NEWS FLASH: You find {} units of {} under a subway seat! Finders keepers.{RESET}",
+            qty,
+            drug.name(),
+            CYAN = COLOR_CYAN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::OfferMade(offer) => {
+            let item = match offer.item {
+                ShopItem::TrenchCoat => "a bigger trench coat (+50 space)".to_string(),
+                ShopItem::Weapon(weapon) => with_article(&weapon_details(rules, weapon)),
+                ShopItem::Ammo => "a box of ammo".to_string(),
+                ShopItem::Armor(armor) => with_article(&armor_details(rules, armor)),
+                ShopItem::Medicine => "some medicine".to_string(),
+            };
             println!(
-                "\t {RED}You have no weapons! You try to run...{RESET}",
-                RED = COLOR_RED,
+                "\t{CYAN}NEWS FLASH: A stranger in a long coat sidles up and offers you {} for ${}.{RESET}",
+                item,
+                offer.price,
+                CYAN = COLOR_CYAN,
+                RESET = COLOR_RESET
+            );
+        }
+        GameEvent::OfferDeclined => println!("\t The stranger shrugs and melts into the crowd."),
+        GameEvent::Tip { drug, city, price } => println!(
+            "\t{CYAN}NEWS FLASH: A friend tips you off: {} is going for ${} in {}.{RESET}",
+            drug.name(),
+            price,
+            city.name(),
+            CYAN = COLOR_CYAN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::ShopOpened(offer) => {
+            println!(
+                "\t {CYAN}Welcome to the black market!{RESET}",
+                CYAN = COLOR_CYAN,
+                RESET = COLOR_RESET
+            );
+            println!("\t You can buy:");
+            print_a_line(COLOR_YELLOW);
+            println!(
+                "\t  1. Larger trench coat (+50 space) for {YELLOW}${}{RESET}",
+                offer.coat_price,
+                YELLOW = COLOR_YELLOW,
+                RESET = COLOR_RESET
+            );
+            println!(
+                "\t  2. Medicine (restore health to full) for {YELLOW}${}{RESET}",
+                offer.med_price,
+                YELLOW = COLOR_YELLOW,
+                RESET = COLOR_RESET
+            );
+            println!(
+                "\t  3. Box of {} rounds of ammo (takes {} space) for {YELLOW}${}{RESET}",
+                rules.ammo_box,
+                rules.ammo_space(rules.ammo_box),
+                offer.ammo_price,
+                YELLOW = COLOR_YELLOW,
                 RESET = COLOR_RESET
             );
-            if self.rng.gen_bool(0.5) {
-                let dmg = self.rng.gen_range(2..=5);
-                self.player.health -= dmg;
+            for (i, price) in offer.weapon_prices.iter().enumerate() {
                 println!(
-                    "\t {RED}You got hurt while escaping! Lost {} health.{RESET}",
-                    dmg,
-                    RED = COLOR_RED,
+                    "\t  {}. {} for {YELLOW}${}{RESET}",
+                    i + 4,
+                    weapon_details(rules, i),
+                    price,
+                    YELLOW = COLOR_YELLOW,
                     RESET = COLOR_RESET
                 );
-            } else {
+            }
+            let first_armor = offer.weapon_prices.len() + 4;
+            for (i, price) in offer.armor_prices.iter().enumerate() {
                 println!(
-                    "\t {GREEN}You barely escape unharmed!{RESET}",
-                    GREEN = COLOR_GREEN,
+                    "\t  {}. {} for {YELLOW}${}{RESET}",
+                    first_armor + i,
+                    armor_details(rules, i),
+                    price,
+                    YELLOW = COLOR_YELLOW,
                     RESET = COLOR_RESET
                 );
             }
-            return;
+            println!("\t  {}. Cancel", first_armor + offer.armor_prices.len());
         }
-        println!(
+        GameEvent::ShopLeft => println!("\t No purchase made."),
+        GameEvent::CoatBought { space } => println!(
+            "\t {GREEN}You bought a larger trench coat! Space is now {}.{RESET}",
+            space,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::WeaponBought { weapon, weapons } => println!(
+            "\t {GREEN}You bought {}! Weapons: {}.{RESET}",
+            with_article(weapon),
+            weapons,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::AmmoBought { rounds } => println!(
+            "\t {GREEN}You bought a box of ammo! You have {} rounds.{RESET}",
+            rounds,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::ArmorBought { armor } => println!(
+            "\t {GREEN}You put on the {}!{RESET}",
+            armor,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::MedicineBought { .. } => println!(
+            "\t {GREEN}You bought medicine and restored your health to full. Yey!!{RESET}",
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::NewsFlash {
+            headline,
+            drug,
+            change,
+            price,
+        } => {
+            println!(
+                "\t{CYAN}NEWS FLASH: {news}{RESET}",
+                CYAN = COLOR_CYAN,
+                news = headline,
+                RESET = COLOR_RESET
+            );
+            let (direction, percent) = match change {
+                PriceChange::Drop(drop) => ("drops", drop),
+                PriceChange::Rise(rise) => ("rises", rise),
+            };
+            println!(
+                "\t{YELLOW}>> {} price {} by {}%! Now: ${}{RESET}",
+                drug.name(),
+                direction,
+                percent,
+                price,
+                YELLOW = COLOR_YELLOW,
+                RESET = COLOR_RESET
+            );
+        }
+        GameEvent::Shortage { drug, city, days } => {
+            println!(
+                "\t{CYAN}NEWS FLASH: Cops bust a {} supply line in {}! Dealers are lying low.{RESET}",
+                drug.name(),
+                city.name(),
+                CYAN = COLOR_CYAN,
+                RESET = COLOR_RESET
+            );
+            println!(
+                "\t{YELLOW}>> No {} to be had in {} for {} days!{RESET}",
+                drug.name(),
+                city.name(),
+                days,
+                YELLOW = COLOR_YELLOW,
+                RESET = COLOR_RESET
+            );
+        }
+        GameEvent::EncounterStarted { headline, .. } => println!(
+            "\t{CYAN}NEWS FLASH: {}{RESET}",
+            headline,
+            CYAN = COLOR_CYAN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::FightStarted { enemy, count } => println!(
             "\t {YELLOW}Fight begins! {enemy} ({}) appear!{RESET}",
-            enemy_count,
+            count,
             YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET,
-            enemy = enemy
-        );
-        let mut round = 1;
-        while enemy_health > 0 && self.player.health > 0 {
+            enemy = enemy.name()
+        ),
+        GameEvent::RoundStarted {
+            round,
+            taunt,
+            player_health,
+            enemy,
+            enemy_health,
+        } => {
             println!(
                 "\n\t {CYAN}--- Round {} ---{RESET}",
                 round,
@@ -475,8 +628,7 @@ impl Game {
                 RESET = COLOR_RESET
             );
             // Funny line
-            if self.rng.gen_bool(0.5) {
-                let line = funny_lines.choose(&mut self.rng).unwrap();
+            if let Some(line) = taunt {
                 println!(
                     "\t {MAGENTA}{}{RESET}",
                     line,
@@ -486,498 +638,1126 @@ impl Game {
             }
             println!(
                 "\t {YELLOW}Your health: {}{RESET}",
-                self.player.health,
+                player_health,
                 YELLOW = COLOR_YELLOW,
                 RESET = COLOR_RESET
             );
             println!(
                 "\t {RED}{} health: {}{RESET}",
-                enemy,
+                enemy.name(),
                 enemy_health,
                 RED = COLOR_RED,
                 RESET = COLOR_RESET
             );
-            print!("\t Do you want to (f)ight or (r)un? ");
-            io::stdout().flush().unwrap();
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            match input.trim() {
-                "f" => {
-                    // Player attacks
-                    let hit = self.rng.gen_range(3..=7);
-                    println!(
-                        "\t {GREEN}You attack and deal {} damage!{RESET}",
-                        hit,
-                        GREEN = COLOR_GREEN,
-                        RESET = COLOR_RESET
-                    );
-                    enemy_health -= hit;
-                    if enemy_health <= 0 {
-                        println!(
-                            "\t {GREEN}You defeated the {}!{RESET}",
-                            enemy,
-                            GREEN = COLOR_GREEN,
-                            RESET = COLOR_RESET
-                        );
-                        let reward = self.rng.gen_range(1000..=5000) * enemy_count;
-                        self.player.cash += reward;
-                        println!(
-                            "\t {YELLOW}Your loot ${}!{RESET}",
-                            reward,
-                            YELLOW = COLOR_YELLOW,
-                            RESET = COLOR_RESET
-                        );
-                        break;
-                    }
-                    // Enemy attacks
-                    // let dmg = self.rng.gen_range(1..=5) * enemy_count; //seems a bit too much damage!
-                    let dmg = self.rng.gen_range(1..=2) * enemy_count;
-                    println!(
-                        "\t {RED}{} attacks and deals {} damage!{RESET}",
-                        enemy,
-                        dmg,
-                        RED = COLOR_RED,
-                        RESET = COLOR_RESET
-                    );
-                    self.player.health -= dmg;
-                    if self.player.health <= 0 {
-                        println!(
-                            "\t {RED}You were defeated by the {}!{RESET}",
-                            enemy,
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                        break;
-                    }
-                }
-                "r" => {
-                    if self.rng.gen_bool(0.5) {
-                        println!(
-                            "\t {GREEN}You escaped the fight!{RESET}",
-                            GREEN = COLOR_GREEN,
-                            RESET = COLOR_RESET
-                        );
-                        break;
-                    } else {
-                        // let dmg = self.rng.gen_range(2..=6); // seems like a too much damage!
-                        let dmg = self.rng.gen_range(1..=3);
-                        self.player.health -= dmg;
-                        println!(
-                            "\t {RED}You failed to escape and took {} damage!{RESET}",
-                            dmg,
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    }
-                }
-                _ => println!(
-                    "\t {YELLOW}You hesitate...{RESET}",
-                    YELLOW = COLOR_YELLOW,
-                    RESET = COLOR_RESET
-                ),
-            }
-            round += 1;
         }
-    }
-
-    // Handles player travel to a new city and advances the day
-    fn travel(&mut self) {
-        println!("\t Where do you want to go?");
-        for (i, city) in City::all().iter().enumerate() {
-            // println!("\t  {}. {}", i + 1, city.name());
-            println!(
-                "\t{my_colour}  {}. {}{RESET}",
-                i + 1,
-                city.name(),
-                my_colour = COLOR_CYAN,
+        GameEvent::PlayerHit { weapon, damage } => println!(
+            "\t {GREEN}You attack with your {} and deal {} damage!{RESET}",
+            weapon,
+            damage,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::PlayerMissed { weapon } => println!(
+            "\t {YELLOW}You attack with your {} and miss!{RESET}",
+            weapon,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::OutOfAmmo { weapon } => println!(
+            "\t {RED}Click! Your {} is out of ammo.{RESET}",
+            weapon,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::ArmorBroke { armor } => println!(
+            "\t {RED}Your {} is shot to pieces! You toss it aside.{RESET}",
+            armor,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::WeaponBroke { weapon } => println!(
+            "\t {RED}Your {} breaks! You toss it aside.{RESET}",
+            weapon,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanHit { damage } => println!(
+            "\t {GREEN}A henchman attacks and deals {} damage!{RESET}",
+            damage,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanMissed => println!(
+            "\t {YELLOW}A henchman attacks and misses!{RESET}",
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanWounded {
+            enemy,
+            damage,
+            health,
+        } => println!(
+            "\t {RED}{} hit one of your henchmen for {} damage! ({} health left){RESET}",
+            enemy.name(),
+            damage,
+            health,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HenchmanKilled { enemy, henchmen } => println!(
+            "\t {RED}{} took out one of your henchmen! Your crew is {} strong.{RESET}",
+            enemy.name(),
+            henchmen,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::EnemyHit {
+            enemy,
+            damage,
+            absorbed,
+        } => println!(
+            "\t {RED}{} attacks and deals {} damage{}!{RESET}",
+            enemy.name(),
+            damage,
+            armor_note(*absorbed),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::EnemyDefeated { enemy, loot } => {
+            println!(
+                "\t {GREEN}You defeated the {}!{RESET}",
+                enemy.name(),
+                GREEN = COLOR_GREEN,
                 RESET = COLOR_RESET
-            ); //does cyan work here?
+            );
+            println!(
+                "\t {YELLOW}Your loot ${}!{RESET}",
+                loot,
+                YELLOW = COLOR_YELLOW,
+                RESET = COLOR_RESET
+            );
         }
-        loop {
-            print!("\t Enter your choice (0 to exit menu): ");
-            io::stdout().flush().unwrap();
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            match input.trim().parse::<usize>() {
-                Ok(0) => {
-                    println!("\t Exiting travel menu.");
-                    break;
-                }
-                Ok(choice) => {
-                    if let Some(city) = City::all().get(choice - 1) {
-                        self.player.city = *city;
-                        self.next_day();
-                        break;
-                    } else {
-                        println!(
-                            "\t {RED}Invalid city choice. Please try again.{RESET}",
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    }
-                }
-                Err(_) => {
-                    println!(
-                        "\t {RED}Invalid input. Please enter a number.{RESET}",
-                        RED = COLOR_RED,
-                        RESET = COLOR_RESET
-                    );
-                }
+        GameEvent::PlayerDefeated { enemy } => println!(
+            "\t {RED}You were defeated by the {}!{RESET}",
+            enemy.name(),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Escaped => println!(
+            "\t {GREEN}You escaped the fight!{RESET}",
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::EscapeFailed { damage, absorbed } => println!(
+            "\t {RED}You failed to escape and took {} damage{}!{RESET}",
+            damage,
+            armor_note(*absorbed),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Bribed { cash } => println!(
+            "\t {GREEN}You slip them ${} and they look the other way.{RESET}",
+            cash,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::BribeRefused { damage, absorbed } => println!(
+            "\t {RED}They won't take your money, and rough you up for {} damage{}!{RESET}",
+            damage,
+            armor_note(*absorbed),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::DrugsDropped { dropped } => {
+            for (drug, qty) in dropped {
+                println!(
+                    "\t {YELLOW}You drop {} {}.{RESET}",
+                    qty,
+                    drug.name(),
+                    YELLOW = COLOR_YELLOW,
+                    RESET = COLOR_RESET
+                );
             }
         }
-    }
-
-    // Main menu for buying, selling, traveling, visiting the loan shark, or shopping around
-    fn buy_sell(&mut self) {
-        // Prompt the player for their next action
-        print!(
-            "\t {CYAN}Do you want to (b)uy, (s)ell, (t)ravel, visit the (l)oan shark, or (h) shop around?{RESET} ",
-            CYAN = COLOR_CYAN,
+        GameEvent::Distracted => println!(
+            "\t {GREEN}They scramble for the drugs and you slip away!{RESET}",
+            GREEN = COLOR_GREEN,
             RESET = COLOR_RESET
-        );
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        // Match the player's input to the corresponding action
-        match input.trim() {
-            "b" => self.buy(),
-            "s" => self.sell(),
-            "t" => self.travel(),
-            "l" => self.loan_shark(),
-            "h" => self.shop_around(),
-            _ => println!(
-                "{RED}Invalid choice.{RESET}",
+        ),
+        GameEvent::DistractionFailed { damage, absorbed } => println!(
+            "\t {RED}They don't fall for it and hit you for {} damage{}!{RESET}",
+            damage,
+            armor_note(*absorbed),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Hidden => println!(
+            "\t {GREEN}You lie low until they give up looking for you.{RESET}",
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::HideFailed { damage, absorbed } => println!(
+            "\t {RED}They find you and you take {} damage{}!{RESET}",
+            damage,
+            armor_note(*absorbed),
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        GameEvent::Arrested {
+            resisted,
+            days,
+            cash,
+            seized,
+        } => {
+            if *resisted {
+                println!(
+                    "\t {RED}The cops knock you out cold and drag you off in cuffs!{RESET}",
+                    RED = COLOR_RED,
+                    RESET = COLOR_RESET
+                );
+            } else {
+                println!(
+                    "\t {YELLOW}You put your hands up and the cops take you in.{RESET}",
+                    YELLOW = COLOR_YELLOW,
+                    RESET = COLOR_RESET
+                );
+            }
+            if *cash > 0 {
+                println!(
+                    "\t {RED}They confiscate ${} of your cash.{RESET}",
+                    cash,
+                    RED = COLOR_RED,
+                    RESET = COLOR_RESET
+                );
+            }
+            for (drug, qty) in seized {
+                println!(
+                    "\t {RED}They confiscate {} of your {}.{RESET}",
+                    qty,
+                    drug.name(),
+                    RED = COLOR_RED,
+                    RESET = COLOR_RESET
+                );
+            }
+            println!(
+                "\t {RED}You spend {} days in jail.{RESET}",
+                days,
                 RED = COLOR_RED,
                 RESET = COLOR_RESET
-            ),
+            );
         }
+        GameEvent::Released { day } => println!(
+            "\t {YELLOW}You walk out of jail on day {}.{RESET}",
+            day,
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        ),
     }
+}
 
-    // Shop around for trench coat upgrades, weapons, or medicine
-    fn shop_around(&mut self) {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let coat_price = rng.gen_range(1000..=4000);
-        let weapon_price = rng.gen_range(1000..=4000);
-        let med_price = rng.gen_range(1000..=4000);
-        println!(
-            "\t {CYAN}Welcome to the black market!{RESET}",
-            CYAN = COLOR_CYAN,
-            RESET = COLOR_RESET
-        );
-        println!("\t You can buy:");
-        print_a_line(COLOR_YELLOW);
+// Shows what the player can do in a round of a fight, with the odds of each, and does it
+fn fight_round(session: &mut Session) {
+    let game = &session.game;
+    let Some(encounter) = game.encounter() else {
+        return;
+    };
+    let rules = game.rules();
+    let city = game.player.city;
+    let mut options = vec![
+        ("f", "ight", fight_odds(game)),
+        (
+            "r",
+            "un",
+            format!("{}% chance of getting away", percent(rules.events.escape)),
+        ),
+        (
+            "h",
+            "ide",
+            format!(
+                "{}% chance that they don't find you in {}",
+                percent(rules.hide_chance(city)),
+                city.name()
+            ),
+        ),
+    ];
+    match encounter.enemy {
+        Enemy::Cops => {
+            options.push((
+                "b",
+                "ribe",
+                format!(
+                    "${}, with a {}% chance that they take it",
+                    rules.bribe_price(encounter.enemy_count, game.player.heat),
+                    percent(rules.events.bribe)
+                ),
+            ));
+            options.push((
+                "s",
+                "urrender",
+                format!(
+                    "they take all the drugs on you and {}% of your cash, and you spend {} days in jail",
+                    percent(rules.confiscation as f64),
+                    rules.jail_days
+                ),
+            ));
+        }
+        Enemy::RivalDealers => options.push((
+            "d",
+            "rop drugs",
+            format!(
+                "you lose {}% of the drugs on you, with a {}% chance that they go for them instead",
+                percent(rules.drop_share as f64),
+                percent(rules.events.distract)
+            ),
+        )),
+        Enemy::Enforcers => {}
+    }
+    for (key, rest, odds) in options {
         println!(
-            "\t  1. Larger trench coat (+50 space) for {YELLOW}${}{RESET}",
-            coat_price,
+            "\t  {YELLOW}({}){}{RESET}: {}",
+            key,
+            rest,
+            odds,
             YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET
         );
-        println!(
-            "\t  2. Weapon (+1) for {YELLOW}${}{RESET}",
-            weapon_price,
+    }
+    match read_input("\t What do you do? ").as_str() {
+        "f" => session.play(Action::Fight),
+        "r" => session.play(Action::Run),
+        "h" => session.play(Action::Hide),
+        "b" => session.play(Action::Bribe),
+        "s" => session.play(Action::Surrender),
+        "d" => session.play(Action::DropDrugs),
+        _ => println!(
+            "\t {YELLOW}You hesitate...{RESET}",
             YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET
-        );
+        ),
+    }
+}
+
+// Describes the chances of the player (and their henchmen) landing a blow
+fn fight_odds(game: &Game) -> String {
+    let rules = game.rules();
+    let weapon = game.player.best_weapon(rules).map(|(_, info)| {
+        let spares = match game.player.spare_weapons(rules) {
+            0 => String::new(),
+            1 => " (backed up by a spare weapon)".to_string(),
+            count => format!(" (backed up by {} spare weapons)", count),
+        };
+        format!(
+            "your {}{} hits {}% of the time",
+            info.name,
+            spares,
+            percent(info.accuracy)
+        )
+    });
+    let crew = match game.player.henchmen.len() {
+        0 => None,
+        1 => Some("your henchman hits".to_string()),
+        count => Some(format!("your {} henchmen hit", count)),
+    }
+    .map(|crew| format!("{} {}% of the time", crew, percent(rules.henchmen.accuracy)));
+    match (weapon, crew) {
+        (Some(weapon), Some(crew)) => format!("{}, {}", weapon, crew),
+        (Some(odds), None) | (None, Some(odds)) => odds,
+        (None, None) => "you have nothing to fight with".to_string(),
+    }
+}
+
+// Turns a chance from 0.0 to 1.0 into a whole percentage
+fn percent(chance: f64) -> i32 {
+    (chance * 100.0).round() as i32
+}
+
+// Asks whether to take a stranger up on their offer
+fn stranger_offer(session: &mut Session) {
+    let input = read_input(&format!(
+        "\t Buy it? You have ${} on you. (y/n) ",
+        session.game.player.cash
+    ));
+    if input == "y" {
+        session.play(Action::AcceptOffer);
+    }
+    // Turned down, or the player could not afford it
+    if session.game.offer().is_some() {
+        session.play(Action::DeclineOffer);
+    }
+}
+
+// Describes how long a trip takes and what it costs, when that is anything but a day for free
+fn trip_details(route: &Route) -> String {
+    let mut details = Vec::new();
+    if route.days > 1 {
+        details.push(format!("{} days", route.days));
+    }
+    if route.cost > 0 {
+        details.push(format!("${}", route.cost));
+    }
+    if details.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", details.join(", "))
+    }
+}
+
+// Handles player travel to a new city and advances the day
+fn travel(session: &mut Session) {
+    println!("\t Where do you want to go?");
+    let routes = session.game.routes().to_vec();
+    for (i, route) in routes.iter().enumerate() {
+        // println!("\t  {}. {}", i + 1, city.name());
         println!(
-            "\t  3. Medicine (restore health to full) for {YELLOW}${}{RESET}",
-            med_price,
-            YELLOW = COLOR_YELLOW,
+            "\t{my_colour}  {}. {}{}{RESET}",
+            i + 1,
+            route.to.name(),
+            trip_details(route),
+            my_colour = COLOR_CYAN,
             RESET = COLOR_RESET
-        );
-        println!("\t  4. Cancel");
-        print!("\t Enter your choice: ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        match input.trim() {
-            "1" => {
-                if self.player.cash >= coat_price {
-                    self.player.cash -= coat_price;
-                    self.player.trenchcoat_space += 50;
-                    println!(
-                        "\t {GREEN}You bought a larger trench coat! Space is now {}.{RESET}",
-                        self.player.trenchcoat_space,
-                        GREEN = COLOR_GREEN,
-                        RESET = COLOR_RESET
-                    );
-                } else {
-                    println!(
-                        "\t {RED}Not enough cash for a larger trench coat.{RESET}",
-                        RED = COLOR_RED,
-                        RESET = COLOR_RESET
-                    );
-                }
-            }
-            "2" => {
-                if self.player.cash >= weapon_price {
-                    self.player.cash -= weapon_price;
-                    self.player.weapons += 1;
-                    println!(
-                        "\t {GREEN}You bought a weapon! Weapons: {}.{RESET}",
-                        self.player.weapons,
-                        GREEN = COLOR_GREEN,
-                        RESET = COLOR_RESET
-                    );
-                } else {
-                    println!(
-                        "\t {RED}Not enough cash for a weapon.{RESET}",
-                        RED = COLOR_RED,
-                        RESET = COLOR_RESET
-                    );
-                }
+        ); //does cyan work here?
+    }
+    loop {
+        let input = read_input("\t Enter your choice (0 to exit menu): ");
+        match input.parse::<usize>() {
+            Ok(0) => {
+                println!("\t Exiting travel menu.");
+                break;
             }
-            "3" => {
-                if self.player.health == MAX_HEALTH {
-                    println!(
-                        "\t {CYAN}Your health is already full!{RESET}",
-                        CYAN = COLOR_CYAN,
-                        RESET = COLOR_RESET
-                    );
-                } else if self.player.cash >= med_price {
-                    self.player.cash -= med_price;
-                    self.player.health = MAX_HEALTH;
-                    println!(
-                        "\t {GREEN}You bought medicine and restored your health to full. Yey!!{RESET}",
-                        GREEN = COLOR_GREEN,
-                        RESET = COLOR_RESET
-                    );
+            Ok(choice) => {
+                if let Some(route) = routes.get(choice - 1) {
+                    session.play(Action::Travel(route.to));
+                    break;
                 } else {
                     println!(
-                        "\t {RED}Not enough cash for medicine.{RESET}",
+                        "\t {RED}Invalid city choice. Please try again.{RESET}",
                         RED = COLOR_RED,
                         RESET = COLOR_RESET
                     );
                 }
             }
-            _ => println!("\t No purchase made."),
-        }
-    }
-
-    // Visit the loan shark to pay off debt
-    fn loan_shark(&mut self) {
-        // Show current debt
-        println!(
-            "\t {MAGENTA}You owe the loan shark: ${}{RESET}",
-            self.player.debt,
-            MAGENTA = COLOR_MAGENTA,
-            RESET = COLOR_RESET
-        );
-        print!("\t How much would you like to pay off? (Enter 0 to cancel) ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        // Parse the amount to pay
-        if let Ok(amount) = input.trim().parse::<i32>() {
-            if amount == 0 {
-                // Player chose not to pay
-                println!("\t No payment made.");
-            } else if amount > self.player.cash {
-                // Not enough cash to pay
-                println!(
-                    "\t {RED}You don't have enough cash to pay that much!{RESET}",
-                    RED = COLOR_RED,
-                    RESET = COLOR_RESET
-                );
-            } else if amount > self.player.debt {
-                // Trying to pay more than owed
+            Err(_) => {
                 println!(
-                    "\t {RED}You don't owe that much!{RESET}",
+                    "\t {RED}Invalid input. Please enter a number.{RESET}",
                     RED = COLOR_RED,
                     RESET = COLOR_RESET
                 );
-            } else {
-                // Deduct payment from cash and debt
-                self.player.cash -= amount;
-                self.player.debt -= amount;
-                println!(
-                    "\t {GREEN}You paid ${} to the loan shark. Remaining debt: ${}{RESET}",
-                    amount,
-                    self.player.debt,
-                    GREEN = COLOR_GREEN,
-                    RESET = COLOR_RESET
-                );
             }
-        } else {
-            // Invalid input handling
+        }
+    }
+}
+
+// Main menu for buying, selling, traveling, visiting the loan shark, or shopping around.
+// Returns false once the player has saved and quit.
+fn buy_sell(session: &mut Session) -> bool {
+    // Prompt the player for their next action (the bank is only open in its own city)
+    let bank_option = if session.game.at_bank() {
+        ", the ban(k)"
+    } else {
+        ""
+    };
+    // ...and the stash house only in its own
+    let stash_option = if session.game.at_stash() {
+        ", your stash h(o)use"
+    } else {
+        ""
+    };
+    let input = read_input(&format!(
+        "\t {CYAN}Do you want to (b)uy, (s)ell, (t)ravel, visit the (l)oan shark{}{}, hire (m)uscle, (h) shop around, or (q) save and quit?{RESET} ",
+        bank_option,
+        stash_option,
+        CYAN = COLOR_CYAN,
+        RESET = COLOR_RESET
+    ));
+    // Match the player's input to the corresponding action
+    match input.as_str() {
+        "b" => buy(session),
+        "s" => sell(session),
+        "t" => travel(session),
+        "l" => loan_shark(session),
+        "h" => shop_around(session),
+        "k" => bank(session),
+        "o" => stash_house(session),
+        "m" => muscle(session),
+        "q" => return !save_game(&session.game),
+        _ => println!(
+            "{RED}Invalid choice.{RESET}",
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+    }
+    true
+}
+
+// Saves the game to a file of the player's choosing. Returns true if it was saved.
+fn save_game(game: &Game) -> bool {
+    let input = read_input(&format!(
+        "\t Save to which file? (Enter for {YELLOW}{}{RESET}) ",
+        DEFAULT_SAVE_FILE,
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    ));
+    let path = if input.is_empty() {
+        DEFAULT_SAVE_FILE
+    } else {
+        input.as_str()
+    };
+    match game.save(path) {
+        Ok(()) => {
+            println!(
+                "\t {GREEN}Game saved to {}. Continue later with --load {}{RESET}",
+                path,
+                path,
+                GREEN = COLOR_GREEN,
+                RESET = COLOR_RESET
+            );
+            true
+        }
+        Err(err) => {
             println!(
-                "\t {RED}Invalid input.{RESET}",
+                "\t {RED}{}{RESET}",
+                err,
                 RED = COLOR_RED,
                 RESET = COLOR_RESET
             );
+            false
         }
     }
+}
 
-    // Handles buying drugs from the market
-    fn buy(&mut self) {
-        // List available drugs and their prices
+// Shop around for trench coat upgrades, weapons, ammo, armor or medicine
+fn shop_around(session: &mut Session) {
+    session.play(Action::ShopAround);
+    let weapons = session.game.rules().weapons.len();
+    let armor = session.game.rules().armor.len();
+    let first_armor = weapons + 4;
+    let item = match read_input("\t Enter your choice: ").parse::<usize>() {
+        Ok(1) => ShopItem::TrenchCoat,
+        Ok(2) => ShopItem::Medicine,
+        Ok(3) => ShopItem::Ammo,
+        Ok(choice) if (4..first_armor).contains(&choice) => ShopItem::Weapon(choice - 4),
+        Ok(choice) if (first_armor..first_armor + armor).contains(&choice) => {
+            ShopItem::Armor(choice - first_armor)
+        }
+        _ => {
+            session.play(Action::LeaveShop);
+            return;
+        }
+    };
+    session.play(Action::ShopBuy(item));
+}
+
+// Visit the loan shark to pay off debt or borrow more
+fn loan_shark(session: &mut Session) {
+    let game = &session.game;
+    let player = &game.player;
+    let rules = game.rules();
+    // Show current debt, and where it is heading if nothing is paid back
+    if player.debt > 0 {
         println!(
-            "\t {CYAN}Which drug do you want to buy?{RESET}",
-            CYAN = COLOR_CYAN,
+            "\t {MAGENTA}You owe {}: ${} at {}% a day.{RESET}",
+            lender_name(rules, player.lender),
+            player.debt,
+            (rules.lender_interest(player.lender) * 100.0) as i32,
+            MAGENTA = COLOR_MAGENTA,
             RESET = COLOR_RESET
         );
-        for (i, drug) in Drug::all().iter().enumerate() {
+        let days_left = rules.start_days - player.day;
+        if days_left > 0 {
             println!(
-                "\t  {YELLOW}{}. {} (${}){RESET}",
-                i + 1,
-                drug.name(),
-                self.prices[drug],
-                YELLOW = COLOR_YELLOW,
+                "\t {MAGENTA}Pay nothing and you'll owe ${} tomorrow and ${} by day {}.{RESET}",
+                game.projected_debt(1),
+                game.projected_debt(days_left),
+                rules.start_days,
+                MAGENTA = COLOR_MAGENTA,
                 RESET = COLOR_RESET
             );
         }
-        print!("\t Enter your choice: ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        // Parse the player's drug choice
-        if let Ok(choice) = input.trim().parse::<usize>() {
-            if let Some(drug) = Drug::all().get(choice - 1) {
-                // Calculate max units that can be bought based on cash and space
-                let max_by_cash = self.player.cash / self.prices[drug];
-                let max_by_space = self.player.trenchcoat_space - self.player.total_drugs();
-                let max_units = max_by_cash.min(max_by_space);
-                // Show the player the max they can buy
-                print!(
-                    "\t How many units? (Max you can buy: {GREEN}{}{RESET}) ",
-                    max_units,
-                    GREEN = COLOR_GREEN,
-                    RESET = COLOR_RESET
-                );
-                io::stdout().flush().unwrap();
-                input.clear();
-                io::stdin().read_line(&mut input).unwrap();
-                // Parse the quantity to buy
-                if let Ok(qty) = input.trim().parse::<i32>() {
-                    let price = self.prices[drug] * qty;
-                    let space = self.player.total_drugs() + qty;
-                    if qty > max_units {
-                        // Trying to buy more than allowed
-                        println!(
-                            "\t {RED}You can't buy that many units.{RESET}",
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    } else if price > self.player.cash {
-                        // Not enough cash
-                        println!(
-                            "\t {RED}Not enough cash.{RESET}",
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    } else if space > self.player.trenchcoat_space {
-                        // Not enough space
-                        println!(
-                            "\t {RED}Not enough space.{RESET}",
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    } else {
-                        // Complete the purchase
-                        self.player.cash -= price;
-                        *self.player.inventory.get_mut(drug).unwrap() += qty;
-                        println!(
-                            "\t {GREEN}Bought {} {}.{RESET}",
-                            qty,
-                            drug.name(),
-                            GREEN = COLOR_GREEN,
-                            RESET = COLOR_RESET
-                        );
-                    }
-                }
+        println!(
+            "\t {MAGENTA}Pay at least ${} by day {}, and never owe more than ${}, or they come for you.{RESET}",
+            (player.debt as f32 * rules.min_payment).ceil() as i32,
+            player.due_day(rules),
+            player.max_debt(rules),
+            MAGENTA = COLOR_MAGENTA,
+            RESET = COLOR_RESET
+        );
+    } else {
+        println!(
+            "\t {GREEN}You don't owe anybody anything.{RESET}",
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        );
+    }
+    println!(
+        "\t {YELLOW}You can borrow up to ${} more.{RESET}",
+        game.available_credit(),
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    let choice = read_input("\t Do you want to (p)ay off debt or (b)orrow? (Enter to cancel) ");
+    let lender = match choice.as_str() {
+        "p" => None,
+        "b" => match choose_lender(session) {
+            Some(lender) => Some(lender),
+            None => return,
+        },
+        _ => {
+            println!("\t No payment made.");
+            return;
+        }
+    };
+    let input = read_input("\t How much? ");
+    // Parse the amount to pay or borrow
+    match (input.parse::<i32>(), lender) {
+        (Ok(amount), None) => session.play(Action::PayDebt(amount)),
+        (Ok(amount), Some(lender)) => session.play(Action::Borrow { lender, amount }),
+        // Invalid input handling
+        (Err(_), _) => println!(
+            "\t {RED}Invalid input.{RESET}",
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+    }
+}
+
+// A weapon's name and how it does in a fight, for the black market and strangers' offers
+fn weapon_details(rules: &Rules, weapon: usize) -> String {
+    match rules.weapon(weapon) {
+        Some(info) => format!(
+            "{} ({}-{} damage, {}% accuracy)",
+            info.name,
+            info.min_damage,
+            info.max_damage,
+            (info.accuracy * 100.0).round() as i32
+        ),
+        None => "weapon".to_string(),
+    }
+}
+
+// A piece of armor's name and how much it protects, for the black market
+fn armor_details(rules: &Rules, armor: usize) -> String {
+    match rules.armor(armor) {
+        Some(info) => format!(
+            "{} (stops {}% of damage, up to {} in all)",
+            info.name,
+            (info.absorb * 100.0).round() as i32,
+            info.durability
+        ),
+        None => "armor".to_string(),
+    }
+}
+
+// Says how much of a hit the player's armor soaked up, if any
+fn armor_note(absorbed: i32) -> String {
+    if absorbed > 0 {
+        format!(" (your armor stopped {})", absorbed)
+    } else {
+        String::new()
+    }
+}
+
+// Puts "a" or "an" in front of a name
+fn with_article(name: &str) -> String {
+    let vowel = name
+        .chars()
+        .next()
+        .is_some_and(|c| "AEIOUaeiou".contains(c));
+    format!("{} {}", if vowel { "an" } else { "a" }, name)
+}
+
+// The weapons that the player carries, best first, or "none"
+fn arsenal(game: &Game) -> String {
+    let rules = game.rules();
+    // Identical weapons (the same kind, just as worn) are counted together
+    let mut counts: Vec<(Weapon, i32)> = Vec::new();
+    for weapon in &game.player.weapons {
+        match counts.iter_mut().find(|(other, _)| other == weapon) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*weapon, 1)),
+        }
+    }
+    counts.sort_by(|(a, _), (b, _)| {
+        let damage = |weapon: &Weapon| {
+            rules
+                .weapon(weapon.kind)
+                .map_or(0.0, |info| info.expected_damage())
+        };
+        damage(b).total_cmp(&damage(a))
+    });
+    let names: Vec<String> = counts
+        .iter()
+        .filter_map(|(weapon, count)| {
+            let mut name = rules.weapon(weapon.kind)?.name.clone();
+            if let Some(attacks) = weapon.attacks_left(rules) {
+                name = format!("{} ({} attacks left)", name, attacks);
             }
+            Some(if *count > 1 {
+                format!("{} x{}", name, count)
+            } else {
+                name
+            })
+        })
+        .collect();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+// The name of a lender, for messages
+fn lender_name(rules: &Rules, lender: usize) -> &str {
+    rules
+        .lender(lender)
+        .map_or("the loan shark", |info| info.name.as_str())
+}
+
+// Picks who to borrow from: the lender already owed, or any of them when debt-free
+fn choose_lender(session: &Session) -> Option<usize> {
+    let rules = session.game.rules();
+    let player = &session.game.player;
+    if player.debt > 0 || rules.lenders.len() == 1 {
+        return Some(player.lender);
+    }
+    for (i, info) in rules.lenders.iter().enumerate() {
+        println!(
+            "\t {}. {} ({}% a day, comes after you past {}x your credit limit, {})",
+            i + 1,
+            info.name,
+            (rules.lender_interest(i) * 100.0) as i32,
+            rules.lender_debt_limit(i),
+            match rules.lender_harshness(i) {
+                chance if chance >= 0.7 => "ruthless",
+                chance if chance >= 0.4 => "firm",
+                _ => "patient",
+            }
+        );
+    }
+    match read_input("\t Who do you want to borrow from? ").parse::<usize>() {
+        Ok(number) if (1..=rules.lenders.len()).contains(&number) => Some(number - 1),
+        _ => {
+            print_rule_error(RuleError::UnknownLender);
+            None
         }
     }
+}
+
+// Handles deposits and withdrawals at the bank
+fn bank(session: &mut Session) {
+    if !session.game.at_bank() {
+        print_rule_error(RuleError::NoBank);
+        return;
+    }
+    println!(
+        "\t {YELLOW}You have ${} in the bank and ${} on you.{RESET}",
+        session.game.player.bank,
+        session.game.player.cash,
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    let choice = read_input("\t Do you want to (d)eposit or (w)ithdraw? (Enter to cancel) ");
+    if choice != "d" && choice != "w" {
+        println!("\t Leaving the bank.");
+        return;
+    }
+    let input = read_input("\t How much? ");
+    match input.parse::<i32>() {
+        Ok(amount) if choice == "d" => session.play(Action::Deposit(amount)),
+        Ok(amount) => session.play(Action::Withdraw(amount)),
+        Err(_) => println!(
+            "\t {RED}Invalid input.{RESET}",
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+    }
+}
 
-    // Handles selling drugs from the player's inventory
-    fn sell(&mut self) {
-        // List available drugs and their prices
+// Handles leaving drugs at the stash house, picking them up and upgrading it
+fn stash_house(session: &mut Session) {
+    let game = &session.game;
+    if !game.at_stash() {
+        print_rule_error(RuleError::NoStash);
+        return;
+    }
+    println!(
+        "\t {YELLOW}Your stash house holds {}/{}.{RESET}",
+        game.player.stash_used(game.rules()),
+        game.player.stash_space(game.rules()),
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    let choice = read_input(&format!(
+        "\t Do you want to (d)rop off drugs, (p)ick up drugs or (u)pgrade (+{} space for ${})? (Enter to cancel) ",
+        game.rules().stash_upgrade_space,
+        game.rules().stash_upgrade_cost
+    ));
+    let (counts, verb) = match choice.as_str() {
+        "d" => (&game.player.inventory, "drop off"),
+        "p" => (&game.player.stash, "pick up"),
+        "u" => {
+            session.play(Action::UpgradeStash);
+            return;
+        }
+        _ => {
+            println!("\t Leaving the stash house.");
+            return;
+        }
+    };
+    // Only the drugs that there are some of can be moved
+    let drugs: Vec<(Drug, i32)> = game
+        .rules()
+        .drug_list()
+        .into_iter()
+        .filter_map(|drug| Some((drug, *counts.get(&drug)?)).filter(|(_, qty)| *qty > 0))
+        .collect();
+    if drugs.is_empty() {
+        println!("\t There's nothing to {}.", verb);
+        return;
+    }
+    for (i, (drug, qty)) in drugs.iter().enumerate() {
         println!(
-            "\t {CYAN}Which drug do you want to sell?{RESET}",
-            CYAN = COLOR_CYAN,
+            "\t  {YELLOW}{}. {} ({}){RESET}",
+            i + 1,
+            drug.name(),
+            qty,
+            YELLOW = COLOR_YELLOW,
             RESET = COLOR_RESET
         );
-        for (i, drug) in Drug::all().iter().enumerate() {
+    }
+    let picked = read_input(&format!("\t Which drug do you want to {}? ", verb))
+        .parse::<usize>()
+        .ok()
+        .and_then(|choice| drugs.get(choice.checked_sub(1)?));
+    let Some(&(drug, _)) = picked else {
+        println!(
+            "\t {RED}Invalid choice.{RESET}",
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        );
+        return;
+    };
+    match read_input("\t How many units? ").parse::<i32>() {
+        Ok(qty) if choice == "d" => session.play(Action::Stash { drug, qty }),
+        Ok(qty) => session.play(Action::Unstash { drug, qty }),
+        Err(_) => println!(
+            "\t {RED}Invalid input.{RESET}",
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+    }
+}
+
+// Handles hiring and letting go of henchmen
+fn muscle(session: &mut Session) {
+    let game = &session.game;
+    let terms = &game.rules().henchmen;
+    println!(
+        "\t {YELLOW}You have {} of {} henchmen, who want ${} a day each.{RESET}",
+        game.player.henchmen.len(),
+        terms.max,
+        terms.wage,
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    );
+    let choice = read_input(&format!(
+        "\t Do you want to (h)ire one for ${} or (l)et one go? (Enter to cancel) ",
+        terms.fee
+    ));
+    match choice.as_str() {
+        "h" => session.play(Action::HireHenchman),
+        "l" => session.play(Action::DismissHenchman),
+        _ => println!("\t Maybe another time."),
+    }
+}
+
+// Shows which way a price has gone since yesterday, in green for up and red for down
+fn price_change(change: Option<i32>) -> String {
+    match change {
+        Some(percent) if percent > 0 => format!(
+            " {GREEN}▲ +{}%{RESET}",
+            percent,
+            GREEN = COLOR_GREEN,
+            RESET = COLOR_RESET
+        ),
+        Some(percent) if percent < 0 => format!(
+            " {RED}▼ {}%{RESET}",
+            percent,
+            RED = COLOR_RED,
+            RESET = COLOR_RESET
+        ),
+        _ => String::new(),
+    }
+}
+
+// Lists the drugs with their current prices and asks the player to pick one
+fn choose_drug(game: &Game, verb: &str) -> Option<Drug> {
+    println!(
+        "\t {CYAN}Which drug do you want to {}?{RESET}",
+        verb,
+        CYAN = COLOR_CYAN,
+        RESET = COLOR_RESET
+    );
+    let drugs = game.rules().drug_list();
+    for (i, drug) in drugs.iter().enumerate() {
+        // Greyed out when nobody is dealing it today
+        if !game.market().is_available(*drug) {
             println!(
-                "\t  {YELLOW}{}. {} (${}){RESET}",
+                "\t  {GREY}{}. {} (none today){RESET}",
                 i + 1,
                 drug.name(),
-                self.prices[drug],
-                YELLOW = COLOR_YELLOW,
+                GREY = COLOR_GREY,
                 RESET = COLOR_RESET
             );
+            continue;
         }
-        print!("\t Enter your choice: ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        // Parse the player's drug choice
-        if let Ok(choice) = input.trim().parse::<usize>() {
-            if let Some(drug) = Drug::all().get(choice - 1) {
-                let owned = self.player.inventory[drug];
-                // Show the player the max they can sell
-                print!(
-                    "\t How many units? (Max you can sell: {GREEN}{}{RESET}) ",
-                    owned,
-                    GREEN = COLOR_GREEN,
-                    RESET = COLOR_RESET
-                );
-                io::stdout().flush().unwrap();
-                input.clear();
-                io::stdin().read_line(&mut input).unwrap();
-                // Parse the quantity to sell
-                if let Ok(qty) = input.trim().parse::<i32>() {
-                    if qty > owned {
-                        // Trying to sell more than owned
-                        println!(
-                            "\t {RED}Not enough to sell.{RESET}",
-                            RED = COLOR_RED,
-                            RESET = COLOR_RESET
-                        );
-                    } else {
-                        // Complete the sale
-                        let price = self.prices[drug] * qty;
-                        self.player.cash += price;
-                        *self.player.inventory.get_mut(drug).unwrap() -= qty;
-                        println!(
-                            "\t {GREEN}Sold {} {}.{RESET}",
-                            qty,
-                            drug.name(),
-                            GREEN = COLOR_GREEN,
-                            RESET = COLOR_RESET
-                        );
-                    }
-                }
-            }
+        println!(
+            "\t  {YELLOW}{}. {} (${}){RESET}",
+            i + 1,
+            drug.name(),
+            game.prices()[drug],
+            YELLOW = COLOR_YELLOW,
+            RESET = COLOR_RESET
+        );
+    }
+    let choice = read_input("\t Enter your choice: ").parse::<usize>().ok()?;
+    let drug = drugs.get(choice.checked_sub(1)?).copied()?;
+    if !game.market().is_available(drug) {
+        print_rule_error(RuleError::SoldOut);
+        return None;
+    }
+    Some(drug)
+}
+
+// Handles buying drugs from the market
+fn buy(session: &mut Session) {
+    let Some(drug) = choose_drug(&session.game, "buy") else {
+        return;
+    };
+    // Show the player the max they can buy
+    let input = read_input(&format!(
+        "\t How many units? (Max you can buy: {GREEN}{}{RESET}) ",
+        session.game.max_buyable(drug),
+        GREEN = COLOR_GREEN,
+        RESET = COLOR_RESET
+    ));
+    // Parse the quantity to buy, and check the price with the player if buying moves it
+    if let Ok(qty) = input.parse::<i32>() {
+        let game = &session.game;
+        if qty <= game.max_buyable(drug)
+            && let Some(cost) = game.buy_cost(drug, qty)
+            && !confirm_trade("cost", cost, qty, game.prices()[&drug])
+        {
+            return;
+        }
+        session.play(Action::Buy { drug, qty });
+    }
+}
+
+// Handles selling drugs from the player's inventory
+fn sell(session: &mut Session) {
+    let Some(drug) = choose_drug(&session.game, "sell") else {
+        return;
+    };
+    // Show the player the max they can sell
+    let input = read_input(&format!(
+        "\t How many units? (Max you can sell: {GREEN}{}{RESET}) ",
+        session
+            .game
+            .player
+            .inventory
+            .get(&drug)
+            .copied()
+            .unwrap_or(0),
+        GREEN = COLOR_GREEN,
+        RESET = COLOR_RESET
+    ));
+    // Parse the quantity to sell, and check the price with the player if selling moves it
+    if let Ok(qty) = input.parse::<i32>() {
+        let game = &session.game;
+        if qty <= game.player.inventory.get(&drug).copied().unwrap_or(0)
+            && let Some(revenue) = game.sell_revenue(drug, qty)
+            && !confirm_trade("bring in", revenue, qty, game.prices()[&drug])
+        {
+            return;
         }
+        session.play(Action::Sell { drug, qty });
+    }
+}
+
+// Big trades move the price, so shows what one will really come to and asks to go ahead.
+// Returns true straight away when the trade is small enough to go at the listed price.
+fn confirm_trade(verb: &str, total: i32, qty: i32, listed: i32) -> bool {
+    if qty <= 0 || total == listed.saturating_mul(qty) {
+        return true;
+    }
+    let input = read_input(&format!(
+        "\t That will {} {YELLOW}${}{RESET} in all, about ${} each (listed at ${}). Go ahead? (y/n) ",
+        verb,
+        total,
+        total / qty,
+        listed,
+        YELLOW = COLOR_YELLOW,
+        RESET = COLOR_RESET
+    ));
+    input.eq_ignore_ascii_case("y")
+}
+
+// Prints the final score and cash at the end of the game
+fn print_final_score(game: &Game) {
+    println!("\n\t Game Over! Final cash: ${}", game.player.cash);
+    if game.player.bank > 0 {
+        println!("\t Money in the bank: ${}", game.player.bank);
     }
+    println!("\t Final score: {}/100", game.final_score());
+    println!(
+        "\t Seed: {} (replay this game with --seed {})",
+        game.seed(),
+        game.seed()
+    );
+}
+
+// Command line options
+#[derive(Default)]
+struct Options {
+    // --seed <number>: start from a fixed seed to reproduce a game
+    seed: Option<u64>,
+    // --load <file>: carry on with a saved game
+    load: Option<String>,
+    // --record <file>: write every action to a replay file
+    record: Option<String>,
+    // --replay <file>: play a replay file back instead of playing
+    replay: Option<String>,
+    // --step: wait for Enter between the steps of a replay
+    step: bool,
+    // --versus <strategy>: compare the final score with a computer player
+    versus: Option<String>,
+    // --rules <file>: play by the rules in this file instead of rules.toml
+    rules: Option<String>,
+}
 
-    // Checks if the game is over due to days, health, or debt
-    fn is_game_over(&self) -> bool {
-        self.player.day > START_DAYS
-            || self.player.health <= 0
-            || self.player.debt > 2 * LOAN_AMOUNT
+// Reads the command line options, exiting with a usage message on anything unexpected
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|value| value.parse::<u64>().ok()) {
+                Some(seed) => options.seed = Some(seed),
+                None => usage_and_exit("--seed needs a whole number"),
+            },
+            "--load" => match args.next() {
+                Some(path) => options.load = Some(path),
+                None => usage_and_exit("--load needs a file name"),
+            },
+            "--record" => match args.next() {
+                Some(path) => options.record = Some(path),
+                None => usage_and_exit("--record needs a file name"),
+            },
+            "--replay" => match args.next() {
+                Some(path) => options.replay = Some(path),
+                None => usage_and_exit("--replay needs a file name"),
+            },
+            "--step" => options.step = true,
+            "--rules" => match args.next() {
+                Some(path) => options.rules = Some(path),
+                None => usage_and_exit("--rules needs a file name"),
+            },
+            "--versus" => match args.next() {
+                Some(name) if STRATEGY_NAMES.contains(&name.as_str()) => {
+                    options.versus = Some(name)
+                }
+                _ => usage_and_exit(&format!(
+                    "--versus needs one of: {}",
+                    STRATEGY_NAMES.join(", ")
+                )),
+            },
+            _ => usage_and_exit(&format!("Unknown option '{}'", arg)),
+        }
+    }
+    // A recording starts from the seed alone, so it cannot pick up a saved game part way through
+    if options.record.is_some() && options.load.is_some() {
+        usage_and_exit("--record cannot be combined with --load");
     }
+    // A saved game carries on from the seed that it was started with
+    if options.seed.is_some() && options.load.is_some() {
+        usage_and_exit("--seed cannot be combined with --load");
+    }
+    options
+}
 
-    // Prints the final score and cash at the end of the game
-    fn print_final_score(&self) {
-        let score = ((self.player.cash as f32) / 1_000_000.0 * 2.0).min(100.0);
-        println!("\n\t Game Over! Final cash: ${}", self.player.cash);
-        println!("\t Final score: {}/100", score.round() as i32);
+// Reads the rules from the given file, or from rules.toml if there is one, quitting if they are broken
+pub(crate) fn load_rules(path: Option<&str>) -> Rules {
+    let result = match path {
+        Some(path) => Rules::load(path),
+        None => Rules::load_or_default(DEFAULT_RULES_FILE),
+    };
+    match result {
+        Ok(rules) => rules,
+        Err(err) => {
+            colour_print(&format!("\t {}", err), "red");
+            std::process::exit(1);
+        }
     }
 }
 
+// Prints how to run the game and quits
+pub(crate) fn usage_and_exit(problem: &str) -> ! {
+    colour_print(&format!("\t {}", problem), "red");
+    println!(
+        "\t Usage: drugwars_rs [--seed <number>] [--rules <file>] [--load <file>] [--record <file>] [--versus <strategy>]"
+    );
+    println!("\t        drugwars_rs --replay <file> [--step]");
+    println!(
+        "\t        drugwars_rs simulate [--games <number>] [--seed <number>] [--rules <file>] [--strategy <name>]"
+    );
+    std::process::exit(2);
+}
+
 // Main function to start the game loop
 fn main() {
+    // The batch simulator has its own options and none of the interactive trimmings
+    if std::env::args().nth(1).as_deref() == Some("simulate") {
+        batch::main(std::env::args().skip(2));
+        return;
+    }
+
+    let options = parse_args();
+
     // Show the banner
     show_banner();
 
     // Display version information from the toml file
     toml_extract::main();
 
-    let mut game = Game::new();
+    if let Some(path) = &options.replay {
+        replay(path, options.step);
+        return;
+    }
+
+    let game = if let Some(path) = &options.load {
+        match Game::load(path) {
+            Ok(game) => {
+                colour_print(&format!("\t Loaded saved game from {}", path), "green");
+                game
+            }
+            Err(err) => {
+                colour_print(&format!("\t {}", err), "red");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let rules = load_rules(options.rules.as_deref());
+        Game::with_rules(options.seed.unwrap_or_else(rand::random), rules)
+            .expect("the rules were checked when they were loaded")
+    };
+    colour_print(&format!("\t Game seed: {}", game.seed()), "purple");
+    let recording = options.record.map(|path| {
+        colour_print(&format!("\t Recording this game to {}", path), "purple");
+        (Recording::new(game.seed(), game.rules().clone()), path)
+    });
+    let mut session = Session { game, recording };
 
-    // The below madness of a loop is used when debugging to remind of the test version of software. Genious, right?! 
+    // The below madness of a loop is used when debugging to remind of the test version of software. Genious, right?!
     let mut counter: u32 = 1;
     while 0 != counter {
         // println!("\r Welcome to Drugwars!");
@@ -985,11 +1765,120 @@ fn main() {
         counter -= 1;
     }
 
-    while !game.is_game_over() {
-        game.print_status();
-        game.buy_sell();
+    while !session.game.is_game_over() {
+        // A fight has to be settled before anything else can happen
+        if session.game.encounter().is_some() {
+            fight_round(&mut session);
+            continue;
+        }
+        // So does a stranger's offer
+        if session.game.offer().is_some() {
+            stranger_offer(&mut session);
+            continue;
+        }
+        print_status(&session.game);
+        if !buy_sell(&mut session) {
+            return;
+        }
+    }
+    print_final_score(&session.game);
+    if let Some(name) = &options.versus {
+        versus(&session.game, name);
+    }
+}
+
+// Lets a computer player loose on the same seed and compares how it did with the player
+fn versus(game: &Game, name: &str) {
+    let mut bot = strategy_by_name(name, game.seed()).expect("strategy name was checked");
+    let summary = run_game(game.seed(), game.rules(), bot.as_mut())
+        .expect("the game's rules were checked when it started");
+    println!(
+        "\n\t {CYAN}The '{}' bot played from the same seed: final cash ${}, score {}/100.{RESET}",
+        name,
+        summary.final_cash,
+        summary.score,
+        CYAN = COLOR_CYAN,
+        RESET = COLOR_RESET
+    );
+    if game.player.total_cash() > summary.final_cash {
+        colour_print("\t You beat the bot!", "green");
+    } else if game.player.total_cash() < summary.final_cash {
+        colour_print("\t The bot beat you!", "red");
+    } else {
+        colour_print("\t It's a tie!", "yellow");
+    }
+}
+
+// Plays a replay file back without asking the player anything, stopping at the first divergence
+fn replay(path: &str, step_by_step: bool) {
+    let recording = match Recording::load(path) {
+        Ok(recording) => recording,
+        Err(err) => {
+            colour_print(&format!("\t {}", err), "red");
+            std::process::exit(1);
+        }
+    };
+    colour_print(
+        &format!(
+            "\t Replaying {} ({} steps, seed {})",
+            path,
+            recording.steps.len(),
+            recording.seed
+        ),
+        "purple",
+    );
+    let mut replayer = match recording.replay() {
+        Ok(replayer) => replayer,
+        Err(err) => {
+            colour_print(&format!("\t {}", err), "red");
+            std::process::exit(1);
+        }
+    };
+    loop {
+        if step_by_step {
+            print_status(replayer.game());
+            read_input("\t Press Enter for the next step... ");
+        }
+        match replayer.step() {
+            None => break,
+            Some(Ok(step)) => {
+                println!(
+                    "\n\t {CYAN}Step {}: {:?}{RESET}",
+                    replayer.steps_played(),
+                    step.action,
+                    CYAN = COLOR_CYAN,
+                    RESET = COLOR_RESET
+                );
+                match &step.outcome {
+                    Outcome::Events(events) => print_events(replayer.game().rules(), events),
+                    Outcome::Refused(err) => print_rule_error(*err),
+                }
+            }
+            Some(Err(divergence)) => {
+                colour_print(
+                    &format!(
+                        "\t Replay diverged at step {}: {:?}",
+                        divergence.step, divergence.action
+                    ),
+                    "red",
+                );
+                println!("\t Recorded: {:?}", divergence.expected);
+                println!("\t Replayed: {:?}", divergence.actual);
+                std::process::exit(1);
+            }
+        }
+    }
+    print_status(replayer.game());
+    colour_print(
+        &format!(
+            "\t Replay finished: all {} steps matched the recording.",
+            replayer.steps_played()
+        ),
+        "green",
+    );
+    if replayer.game().is_game_over() {
+        print_final_score(replayer.game());
     }
-    game.print_final_score();
 }
 
 // Function to display the cheapish-looking banner
@@ -1018,7 +1907,7 @@ fn show_banner() {
 }
 
 // Print colored text to the console
-fn colour_print(text: &str, colour: &str) {
+pub(crate) fn colour_print(text: &str, colour: &str) {
     match colour {
         "flush_green" => {
             print!("\x1b[2K\r"); // Clear the line and move to the beginning
@@ -0,0 +1,532 @@
+// Drugs, cities and the markets in each city
+use crate::rules::Rules;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// A drug on the market, known by its name in the drug catalogue (see `DrugInfo`).
+// It is as cheap to copy and compare as the old enum was, and is written to files by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Drug(&'static str);
+
+// Every drug and city name seen so far, so that each one is only ever leaked once
+static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+// For turning a name from a data file into one that lives as long as the program
+fn intern(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(known) = names.iter().find(|known| **known == name) {
+        return known;
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.push(leaked);
+    leaked
+}
+
+impl Drug {
+    // For looking up a drug by its name
+    pub fn named(name: &str) -> Drug {
+        Drug(intern(name))
+    }
+    // For returning the display name for each drug
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl Serialize for Drug {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Drug {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Drug::named(&name))
+    }
+}
+
+// One entry of the drug catalogue: everything the market needs to know about a drug
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DrugInfo {
+    pub name: Drug,     // The name shown to the player
+    pub min_price: i32, // The lowest everyday price
+    pub max_price: i32, // The highest everyday price
+    // How wild the price is, both from day to day and in news flashes: 1.0 is normal,
+    // 2.0 twice as much
+    #[serde(default = "DrugInfo::default_volatility")]
+    pub volatility: f32,
+    // How strongly the price is pulled back towards the middle of its range each day, from
+    // 0.0 (not at all) to 1.0 (straight back). Only used by the random-walk price model.
+    #[serde(default = "DrugInfo::default_reversion")]
+    pub reversion: f32,
+    // How much trench coat space each unit takes up
+    #[serde(default = "DrugInfo::default_space")]
+    pub space: i32,
+    // How many units can be bought or sold before the price moves by 10%
+    #[serde(default = "DrugInfo::default_depth")]
+    pub depth: i32,
+    pub drop_headlines: Vec<String>, // News flashes for a sudden price drop
+    pub rise_headlines: Vec<String>, // News flashes for a sudden price spike
+}
+
+impl DrugInfo {
+    fn default_volatility() -> f32 {
+        1.0
+    }
+
+    fn default_reversion() -> f32 {
+        0.15
+    }
+
+    fn default_space() -> i32 {
+        1
+    }
+
+    fn default_depth() -> i32 {
+        50
+    }
+
+    // How far each unit traded moves the price, on a log scale
+    fn impact_per_unit(&self) -> f64 {
+        1.1f64.ln() / self.depth as f64
+    }
+
+    // What buying `qty` units costs in all when the price is `price`.
+    // The first unit goes at that price and every unit bought pushes the price of the next one
+    // up a step, so buying a unit at a time costs no less than buying in one go.
+    pub fn buy_cost(&self, price: f64, qty: i32) -> i32 {
+        if qty <= 0 {
+            return 0;
+        }
+        let step = self.impact_per_unit().exp();
+        let total = price * (step.powi(qty) - 1.0) / (step - 1.0);
+        // Dealers round in their own favour, so splitting a deal up never saves anything
+        (total - 1e-6).ceil() as i32
+    }
+
+    // What selling `qty` units brings in when the price is `price`.
+    // Every unit sold goes a step below the price before it, so selling straight back what was
+    // just bought brings in no more than it cost.
+    pub fn sell_revenue(&self, price: f64, qty: i32) -> i32 {
+        if qty <= 0 {
+            return 0;
+        }
+        let step = (-self.impact_per_unit()).exp();
+        let total = price * step * (1.0 - step.powi(qty)) / (1.0 - step);
+        (total + 1e-6).floor() as i32
+    }
+
+    // The most units (up to `limit`) that `cash` can buy when the price is `price`
+    pub fn max_affordable(&self, price: f64, cash: i32, limit: i32) -> i32 {
+        let (mut low, mut high) = (0, limit.max(0));
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if self.buy_cost(price, mid) <= cash {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    // Checks that the entry makes sense
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let name = self.name.name();
+        if name.trim().is_empty() {
+            return Err("every drug needs a name".to_string());
+        }
+        if self.min_price < 1 || self.max_price < self.min_price {
+            return Err(format!(
+                "{} needs a min_price of at least 1 and a max_price no lower than that",
+                name
+            ));
+        }
+        if !(self.volatility > 0.0 && self.volatility <= 10.0) {
+            return Err(format!(
+                "{} needs a volatility above 0.0 and up to 10.0",
+                name
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.reversion) {
+            return Err(format!("{} needs a reversion from 0.0 to 1.0", name));
+        }
+        if self.space < 1 {
+            return Err(format!("{} needs to take up at least 1 space", name));
+        }
+        if self.depth < 1 {
+            return Err(format!("{} needs a depth of at least 1", name));
+        }
+        if self.drop_headlines.is_empty() || self.rise_headlines.is_empty() {
+            return Err(format!(
+                "{} needs at least one drop headline and one rise headline",
+                name
+            ));
+        }
+        Ok(())
+    }
+}
+
+// The drugs that the game ships with, as listed in src/drugs.toml
+#[derive(Deserialize)]
+struct Catalogue {
+    drugs: Vec<DrugInfo>,
+}
+
+pub(crate) fn standard_drugs() -> Vec<DrugInfo> {
+    let catalogue: Catalogue =
+        toml::from_str(include_str!("drugs.toml")).expect("src/drugs.toml is valid");
+    catalogue.drugs
+}
+
+// A place on the map, known by its name (see `CityInfo`). Written to files by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct City(&'static str);
+
+impl City {
+    // For looking up a city by its name
+    pub fn named(name: &str) -> City {
+        City(intern(name))
+    }
+    // For returning the display name for each city
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl Serialize for City {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for City {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(City::named(&name))
+    }
+}
+
+// One city on the map: where you can get to from there, and how it differs from elsewhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CityInfo {
+    pub name: City,         // The name shown to the player
+    pub routes: Vec<Route>, // The cities that can be reached from here
+    // Drug prices here compared to the everyday range (1.0 for any drug not listed)
+    #[serde(default)]
+    pub price_multipliers: HashMap<Drug, f32>,
+    // How much more (or less) likely random events are here
+    #[serde(default)]
+    pub risk: EventRisk,
+}
+
+// A way from one city to another
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub to: City, // Where the route leads
+    // How many days the trip takes
+    #[serde(default = "Route::default_days")]
+    pub days: i32,
+    // What the trip costs in cash
+    #[serde(default)]
+    pub cost: i32,
+}
+
+impl Route {
+    fn default_days() -> i32 {
+        1
+    }
+}
+
+// Multipliers for the chances of random events in a city: 1.0 is normal, 2.0 twice as likely
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventRisk {
+    pub news_flash: f64, // News flashes that move a drug price
+    pub fight: f64,      // Cops or rival dealers picking a fight
+    pub cops: f64,       // A fight being with the cops rather than rival dealers
+    pub hide: f64,       // Hiding from a fight working
+}
+
+impl Default for EventRisk {
+    fn default() -> Self {
+        EventRisk {
+            news_flash: 1.0,
+            fight: 1.0,
+            cops: 1.0,
+            hide: 1.0,
+        }
+    }
+}
+
+impl CityInfo {
+    // The price multiplier for a drug in this city
+    pub fn price_multiplier(&self, drug: Drug) -> f32 {
+        self.price_multipliers.get(&drug).copied().unwrap_or(1.0)
+    }
+
+    // The route from here to another city, if there is one
+    pub fn route_to(&self, city: City) -> Option<&Route> {
+        self.routes.iter().find(|route| route.to == city)
+    }
+
+    // Checks that the entry makes sense on its own (the rules check the routes and drugs)
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let name = self.name.name();
+        if name.trim().is_empty() {
+            return Err("every city needs a name".to_string());
+        }
+        if self.routes.is_empty() {
+            return Err(format!("{} needs at least one route out", name));
+        }
+        for route in &self.routes {
+            if route.to == self.name {
+                return Err(format!("{} has a route to itself", name));
+            }
+            if route.days < 1 || route.cost < 0 {
+                return Err(format!(
+                    "the route from {} to {} needs at least 1 day and a cost of 0 or more",
+                    name,
+                    route.to.name()
+                ));
+            }
+        }
+        // A player who is broke has to be able to get out of town, or the game can't go on
+        if self.routes.iter().all(|route| route.cost > 0) {
+            return Err(format!(
+                "{} needs at least one route out that costs nothing",
+                name
+            ));
+        }
+        if self.price_multipliers.values().any(|mult| *mult <= 0.0) {
+            return Err(format!(
+                "{} has a price multiplier that is not above 0.0",
+                name
+            ));
+        }
+        let risk = &self.risk;
+        if risk.news_flash < 0.0 || risk.fight < 0.0 || risk.cops < 0.0 || risk.hide < 0.0 {
+            return Err(format!("{} has a negative event risk", name));
+        }
+        Ok(())
+    }
+}
+
+// The map that the game ships with, as listed in src/cities.toml
+#[derive(Deserialize)]
+struct Map {
+    cities: Vec<CityInfo>,
+}
+
+pub(crate) fn standard_cities() -> Vec<CityInfo> {
+    let map: Map = toml::from_str(include_str!("cities.toml")).expect("src/cities.toml is valid");
+    map.cities
+}
+
+// How prices move from one day to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriceModel {
+    // Every price is drawn afresh from its everyday range each day, as in the original game
+    Classic,
+    // Prices wander away from yesterday's, following a trend that changes slowly, and are
+    // pulled back towards the middle of their range
+    #[default]
+    RandomWalk,
+}
+
+// How much of the player's own effect on a price is still there the next day
+const IMPACT_MEMORY: f32 = 0.5;
+
+// The largest daily random move of a price, as a share of its range (on a log scale)
+const DAILY_SWING: f32 = 0.08;
+// How much of yesterday's trend carries over to today
+const TREND_MEMORY: f32 = 0.8;
+// The largest daily change of a trend, as a share of the price range (on a log scale)
+const TREND_SWING: f32 = 0.025;
+
+// The drug market in one city. Every city keeps its own market, which moves on each day
+// whether or not the player is there to see it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Market {
+    pub prices: HashMap<Drug, i32>, // Today's prices
+    // Yesterday's prices, to see which way things are going
+    pub previous: HashMap<Drug, i32>,
+    // How fast each price is drifting, per day on a log scale (random-walk model only)
+    pub trends: HashMap<Drug, f32>,
+    // How far the player's own buying and selling has pushed each price, on a log scale.
+    // It wears off over the following days.
+    pub impact: HashMap<Drug, f32>,
+    // How far the player's trades today have left each price from the whole dollar that it is
+    // listed at, on a log scale, so that trading a unit at a time moves it as much as trading
+    // in one go
+    pub rounding: HashMap<Drug, f32>,
+    // Drugs that nobody is dealing here, with how many more days that lasts (counting today)
+    pub unavailable: HashMap<Drug, i32>,
+}
+
+impl Market {
+    // Opens the market in a city on the first day
+    pub(crate) fn new<R: Rng>(rules: &Rules, city: &CityInfo, rng: &mut R) -> Market {
+        let mut market = Market {
+            prices: generate_prices(&rules.drugs, city, rng),
+            previous: HashMap::new(),
+            trends: HashMap::new(),
+            impact: HashMap::new(),
+            rounding: HashMap::new(),
+            unavailable: HashMap::new(),
+        };
+        market.sell_out(rules, rng);
+        market
+    }
+
+    // Whether a drug can be bought or sold here today
+    pub fn is_available(&self, drug: Drug) -> bool {
+        self.prices.contains_key(&drug) && !self.unavailable.contains_key(&drug)
+    }
+
+    // Stops a drug being dealt here for some days (counting today), unless it is already
+    // gone for longer
+    pub(crate) fn shortage(&mut self, drug: Drug, days: i32) {
+        let left = self.unavailable.entry(drug).or_insert(0);
+        *left = (*left).max(days);
+    }
+
+    // Some drugs are simply not around today, as in the original game
+    fn sell_out<R: Rng>(&mut self, rules: &Rules, rng: &mut R) {
+        for info in &rules.drugs {
+            if rng.gen_bool(rules.events.sold_out) {
+                self.shortage(info.name, 1);
+            }
+        }
+    }
+
+    // Moves the market on to the next day
+    pub(crate) fn next_day<R: Rng>(&mut self, rules: &Rules, city: &CityInfo, rng: &mut R) {
+        let drugs = &rules.drugs;
+        self.previous = self.prices.clone();
+        self.rounding.clear();
+        // Shortages run their course
+        self.unavailable.retain(|_, days| {
+            *days -= 1;
+            *days > 0
+        });
+        // Take the player's own impact out so that the market moves from where it would be
+        self.shift_prices(-1.0);
+        match rules.price_model {
+            PriceModel::Classic => self.prices = generate_prices(drugs, city, rng),
+            PriceModel::RandomWalk => {
+                for info in drugs {
+                    self.walk(info, city.price_multiplier(info.name), rng);
+                }
+            }
+        }
+        // Then put back what is left of it
+        self.impact.retain(|_, impact| {
+            *impact *= IMPACT_MEMORY;
+            impact.abs() > 0.001
+        });
+        self.shift_prices(1.0);
+        self.sell_out(rules, rng);
+    }
+
+    // Moves every price by the player's impact on it, or back again when `direction` is -1.0
+    fn shift_prices(&mut self, direction: f32) {
+        for (drug, impact) in &self.impact {
+            if let Some(price) = self.prices.get_mut(drug) {
+                *price = (*price as f32 * (impact * direction).exp())
+                    .round()
+                    .max(1.0) as i32;
+            }
+        }
+    }
+
+    // The price of a drug before it is rounded to the listed whole dollar
+    pub fn exact_price(&self, drug: Drug) -> Option<f64> {
+        let price = *self.prices.get(&drug)? as f64;
+        let rounding = self.rounding.get(&drug).copied().unwrap_or(0.0) as f64;
+        Some(price * rounding.exp())
+    }
+
+    // Moves a price after the player buys (positive `qty`) or sells (negative `qty`) some units,
+    // a step for every unit
+    pub(crate) fn trade(&mut self, info: &DrugInfo, qty: i32) {
+        let shift = info.impact_per_unit() * qty as f64;
+        if let Some(exact) = self.exact_price(info.name) {
+            let exact = exact * shift.exp();
+            let listed = exact.round().max(1.0);
+            self.prices.insert(info.name, listed as i32);
+            self.rounding
+                .insert(info.name, (exact / listed).ln() as f32);
+        }
+        *self.impact.entry(info.name).or_insert(0.0) += shift as f32;
+    }
+
+    // Moves one price along its random walk, keeping it inside the everyday range
+    fn walk<R: Rng>(&mut self, info: &DrugInfo, multiplier: f32, rng: &mut R) {
+        let low = (info.min_price as f32 * multiplier).max(1.0);
+        let high = (info.max_price as f32 * multiplier).max(low);
+        let base = (low + high) / 2.0;
+        // Everything happens on a log scale, so that cheap and expensive drugs move alike
+        let spread = (high / low).ln() * info.volatility;
+        let trend = self.trends.entry(info.name).or_insert(0.0);
+        *trend = *trend * TREND_MEMORY + rng.gen_range(-1.0..=1.0) * TREND_SWING * spread;
+        let price = self
+            .prices
+            .get(&info.name)
+            .map_or(base, |p| (*p).max(1) as f32);
+        let pull = info.reversion * (base.ln() - price.ln());
+        let shock = rng.gen_range(-1.0..=1.0) * DAILY_SWING * spread;
+        let next = (price.ln() + pull + *trend + shock).exp().clamp(low, high);
+        self.prices.insert(info.name, next.round() as i32);
+    }
+
+    // How much a price has changed since yesterday, in percent
+    pub fn change(&self, drug: Drug) -> Option<i32> {
+        let before = *self.previous.get(&drug)?;
+        let now = *self.prices.get(&drug)?;
+        if before <= 0 {
+            return None;
+        }
+        Some(((now - before) as f32 * 100.0 / before as f32).round() as i32)
+    }
+}
+
+// For generating random prices for each drug in a city for the current day
+pub(crate) fn generate_prices<R: Rng>(
+    drugs: &[DrugInfo],
+    city: &CityInfo,
+    rng: &mut R,
+) -> HashMap<Drug, i32> {
+    let mut prices = HashMap::new();
+    for info in drugs {
+        let price = rng.gen_range(info.min_price..=info.max_price);
+        let price = (price as f32 * city.price_multiplier(info.name))
+            .round()
+            .max(1.0) as i32;
+        prices.insert(info.name, price);
+    }
+    prices
+}
+
+// The headline for a news flash about a drug: `rising` picks a price spike over a price drop
+pub(crate) fn news_headline<R: Rng>(info: &DrugInfo, rising: bool, rng: &mut R) -> String {
+    let headlines = if rising {
+        &info.rise_headlines
+    } else {
+        &info.drop_headlines
+    };
+    // Only roll the dice when there is a choice, so single-headline drugs use no randomness
+    if headlines.len() == 1 {
+        headlines[0].clone()
+    } else {
+        headlines.choose(rng).unwrap().clone()
+    }
+}
@@ -0,0 +1,212 @@
+// The player and everything they carry around
+use crate::market::{City, Drug};
+use crate::rules::{Rules, WeaponInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Every spare weapon carried (one that could be fought with, besides the best) makes each hit
+// this much harder, as the player has something to fall back on once the first blow lands
+const SPARE_WEAPON_BONUS: f64 = 0.1;
+// There are only so many hands, so any more spare weapons than this make no difference
+const MAX_SPARE_WEAPONS: usize = 3;
+
+// Struct for representing the player and their state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    pub cash: i32, // Player's current cash
+    pub debt: i32, // Player's current debt
+    // The lender that the debt is owed to (a number into the rules' list of lenders)
+    pub lender: usize,
+    // Everything paid back on earlier days beyond what was borrowed, which raises the credit limit
+    pub repaid: i32,
+    // Borrowed and paid back today. Only paying back more than was borrowed earns credit, and
+    // that is settled overnight, so shuffling money back and forth in one day proves nothing.
+    pub borrowed_today: i32,
+    pub paid_today: i32,
+    // The day of the last payment that the lender was happy with (or of a fresh loan)
+    pub last_paid: i32,
+    // How far the lender has gone to collect an overdue debt (0 until the first warning)
+    pub enforcement: i32,
+    // Cash in the bank: it earns interest, and nobody can take it off the player
+    pub bank: i32,
+    pub health: i32,                   // Player's health (max 10 by default)
+    pub trenchcoat_space: i32,         // Max inventory space
+    pub inventory: HashMap<Drug, i32>, // Drug inventory
+    // Drugs kept in the stash house, outside the trench coat
+    pub stash: HashMap<Drug, i32>,
+    // How many times the stash house has been made bigger
+    pub stash_upgrades: i32,
+    pub weapons: Vec<Weapon>, // The weapons carried
+    // Rounds of ammunition carried (they take up trench coat space)
+    pub ammo: i32,
+    // The body armor worn, if any
+    pub armor: Option<Armor>,
+    // The henchmen hired, by how much health each of them has left
+    pub henchmen: Vec<i32>,
+    // How hot the cops are on the player's trail (the more heat, the bigger the bribe)
+    pub heat: i32,
+    pub day: i32,   // Current day
+    pub city: City, // Current city
+}
+
+impl Player {
+    // For creating new player with initial values (from the rules)
+    // Note these values can be changed in rules.toml to test the game
+    // or to make it easier to play!
+    // For example, you can set start_cash to 2000000 to start with 2 million cash
+    // or set start_space to 1000 to start with 1000 trenchcoat space
+    // or set max_health to 100 to start with 100 health
+    // or set start_days to 100 to have 100 days to play
+    // or set loan_amount to 100000 to start with 100k debt
+    // or set loan_interest to 0.05 to have 5% daily interest on the loan
+    // or set start_cash to 1000000 and start_space to 1000 to start with 1 million cash and 1000 space
+    // or set max_health to 100 and start_days to 100 to have 100 health and 100 days to play
+    // or set start_weapons to 10 so that each finger gets one!
+    // But you don't need that, right?!!
+
+    pub fn new(rules: &Rules) -> Self {
+        let mut inventory = HashMap::new();
+        for drug in rules.drug_list() {
+            inventory.insert(drug, 0);
+        }
+        Player {
+            cash: rules.start_cash,
+            debt: rules.loan_amount,
+            lender: 0,
+            repaid: 0,
+            borrowed_today: 0,
+            paid_today: 0,
+            last_paid: 1,
+            enforcement: 0,
+            bank: 0,
+            health: rules.max_health,
+            trenchcoat_space: rules.start_space,
+            inventory,
+            stash: HashMap::new(),
+            stash_upgrades: 0,
+            // weapons: 0, //used for debugging ... you never know when you might need it!
+            weapons: vec![Weapon::new(0); rules.start_weapons as usize],
+            ammo: 0,
+            armor: None,
+            henchmen: Vec::new(),
+            heat: 0,
+            day: 1,
+            city: rules.start_city(),
+        }
+    }
+    // For returning the cash on hand plus the money in the bank
+    pub fn total_cash(&self) -> i32 {
+        self.cash.saturating_add(self.bank)
+    }
+    // For returning the space in the stash house, upgrades included
+    pub fn stash_space(&self, rules: &Rules) -> i32 {
+        rules.stash_space + self.stash_upgrades * rules.stash_upgrade_space
+    }
+    // For returning the stash house space taken up by the drugs kept there
+    pub fn stash_used(&self, rules: &Rules) -> i32 {
+        self.stash
+            .iter()
+            .map(|(drug, qty)| qty * rules.drug_info(*drug).map_or(1, |info| info.space))
+            .sum()
+    }
+    // For returning how much the player may owe in all: the starting loan plus everything repaid
+    // before today
+    pub fn credit_limit(&self, rules: &Rules) -> i32 {
+        rules.loan_amount.saturating_add(self.repaid)
+    }
+    // For returning the debt past which the lender wants paying at once
+    pub fn max_debt(&self, rules: &Rules) -> i32 {
+        self.credit_limit(rules)
+            .saturating_mul(rules.lender_debt_limit(self.lender))
+    }
+    // For returning the last day to make a payment before the debt is overdue
+    pub fn due_day(&self, rules: &Rules) -> i32 {
+        self.last_paid + rules.repayment_days
+    }
+    // For returning whether the lender is out of patience: the payment is late, or the debt
+    // has grown too big
+    pub fn is_overdue(&self, rules: &Rules) -> bool {
+        self.debt > 0 && (self.day > self.due_day(rules) || self.debt > self.max_debt(rules))
+    }
+    // For returning the weapon that the player fights with: the one that does the most damage
+    // out of those with enough ammunition for an attack. Also returns where it is in `weapons`.
+    pub fn best_weapon<'a>(&self, rules: &'a Rules) -> Option<(usize, &'a WeaponInfo)> {
+        self.weapons
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, weapon)| Some((slot, rules.weapon(weapon.kind)?)))
+            .filter(|(_, info)| info.ammo <= self.ammo)
+            .max_by(|(_, a), (_, b)| a.expected_damage().total_cmp(&b.expected_damage()))
+    }
+    // For returning how many spare weapons count in a fight: the usable weapons besides the
+    // best one, up to a limit
+    pub fn spare_weapons(&self, rules: &Rules) -> usize {
+        let usable = self
+            .weapons
+            .iter()
+            .filter_map(|weapon| rules.weapon(weapon.kind))
+            .filter(|info| info.ammo <= self.ammo)
+            .count();
+        usable.saturating_sub(1).min(MAX_SPARE_WEAPONS)
+    }
+    // For returning what every hit with the best weapon is multiplied by, thanks to the spares
+    pub fn damage_bonus(&self, rules: &Rules) -> f64 {
+        1.0 + SPARE_WEAPON_BONUS * self.spare_weapons(rules) as f64
+    }
+    // For returning the total number of drugs carried
+    pub fn total_drugs(&self) -> i32 {
+        self.inventory.values().sum()
+    }
+    // For returning the trench coat space taken up by the drugs carried
+    pub fn space_used(&self, rules: &Rules) -> i32 {
+        let drugs: i32 = self
+            .inventory
+            .iter()
+            .map(|(drug, qty)| qty * rules.drug_info(*drug).map_or(1, |info| info.space))
+            .sum();
+        drugs + rules.ammo_space(self.ammo)
+    }
+    // For returning the trench coat space that is still free
+    pub fn free_space(&self, rules: &Rules) -> i32 {
+        self.trenchcoat_space - self.space_used(rules)
+    }
+}
+
+// A weapon that the player carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Weapon {
+    pub kind: usize, // What it is (a number into the rules' weapon catalogue)
+    pub wear: i32,   // Attacks made with it so far
+}
+
+impl Weapon {
+    // For creating a weapon fresh from the black market
+    pub fn new(kind: usize) -> Self {
+        Weapon { kind, wear: 0 }
+    }
+    // For returning how many more attacks it lasts, if it wears out at all
+    pub fn attacks_left(&self, rules: &Rules) -> Option<i32> {
+        let durability = rules.weapon(self.kind)?.durability?;
+        Some(durability - self.wear)
+    }
+}
+
+// A piece of body armor that the player wears
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Armor {
+    pub kind: usize, // What it is (a number into the rules' armor catalogue)
+    pub wear: i32,   // The damage that it has soaked up so far
+}
+
+impl Armor {
+    // For creating armor fresh from the black market
+    pub fn new(kind: usize) -> Self {
+        Armor { kind, wear: 0 }
+    }
+    // For returning how much more damage it can soak up before it falls apart
+    pub fn absorb_left(&self, rules: &Rules) -> i32 {
+        rules
+            .armor(self.kind)
+            .map_or(0, |info| info.durability - self.wear)
+    }
+}
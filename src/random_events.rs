@@ -0,0 +1,449 @@
+// The random events that can happen when the player arrives somewhere.
+// Each event says when it can happen, how likely it is and what it does; `Game::next_day` rolls
+// every event in the registry in turn. Events can be added, removed or re-weighted through
+// `Game::random_events_mut` without touching the day loop.
+use crate::action::{Enemy, GameEvent, PriceChange, ShopItem, StrangerOffer};
+use crate::game::Game;
+use crate::market::{City, Drug, news_headline};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+// Something that may happen to the player on arrival
+pub trait RandomEvent {
+    // A short name, for finding the event in the registry
+    fn name(&self) -> &str;
+
+    // Whether the event can happen at all today (depending on the city, the day, the player...).
+    // Events that are not eligible are skipped without a roll.
+    fn is_eligible(&self, _game: &Game) -> bool {
+        true
+    }
+
+    // The chance (from 0.0 to 1.0) that the event happens today
+    fn weight(&self, game: &Game) -> f64;
+
+    // Makes the event happen
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>);
+}
+
+// The random events of a game, rolled in the order they were registered
+#[derive(Default)]
+pub struct EventRegistry {
+    events: Vec<Box<dyn RandomEvent>>,
+}
+
+impl EventRegistry {
+    // The events that the game ships with, in the order they have always been rolled
+    pub fn standard() -> Self {
+        let mut registry = EventRegistry::default();
+        registry.register(Box::new(NewsFlash));
+        registry.register(Box::new(Shortage));
+        registry.register(Box::new(StashRaid));
+        registry.register(Box::new(Mugging));
+        registry.register(Box::new(FoundDrugs));
+        registry.register(Box::new(Tip));
+        registry.register(Box::new(LoanEnforcement));
+        registry.register(Box::new(Fight));
+        registry.register(Box::new(Stranger));
+        registry
+    }
+
+    // Adds an event, to be rolled after the ones already registered
+    pub fn register(&mut self, event: Box<dyn RandomEvent>) {
+        self.events.push(event);
+    }
+
+    // Takes an event out of the game. Returns whether there was one by that name.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.events.len();
+        self.events.retain(|event| event.name() != name);
+        self.events.len() < before
+    }
+
+    // The names of the registered events, in the order they are rolled
+    pub fn names(&self) -> Vec<&str> {
+        self.events.iter().map(|event| event.name()).collect()
+    }
+
+    // Rolls every eligible event and applies the ones that come up
+    pub(crate) fn roll(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        for event in &self.events {
+            if !event.is_eligible(game) {
+                continue;
+            }
+            let chance = event.weight(game).clamp(0.0, 1.0);
+            if game.rng.gen_bool(chance) {
+                event.apply(game, events);
+            }
+        }
+    }
+}
+
+// News flashes that cause sudden price changes in the local market
+pub struct NewsFlash;
+
+impl RandomEvent for NewsFlash {
+    fn name(&self) -> &str {
+        "news_flash"
+    }
+
+    // 30% chance each day (by default), more or less depending on the city
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.news_flash * game.here().risk.news_flash
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        // Pick a random drug
+        let info = game.rules.drugs.choose(&mut game.rng).unwrap();
+        let drug = info.name;
+        // Pick a random event: 0 = price drop, 1 = price spike
+        let event = game.rng.gen_range(0..=1);
+        let headline = news_headline(info, event == 1, &mut game.rng);
+        // Apply the price change to the local market, scaled by how volatile the drug is
+        let market = game.markets.get_mut(&game.player.city).unwrap();
+        let price = market.prices.get_mut(&drug).unwrap();
+        let change = if event == 0 {
+            // Sudden drop: 40-70% off (never more than 95%)
+            let drop = game.rng.gen_range(40..=70);
+            let drop = ((drop as f32 * info.volatility).round() as i32).min(95);
+            *price = (*price as f32 * (1.0 - drop as f32 / 100.0)).max(1.0) as i32;
+            PriceChange::Drop(drop)
+        } else {
+            // Sudden rise: 50-120% up
+            let rise = game.rng.gen_range(50..=120);
+            let rise = (rise as f32 * info.volatility).round() as i32;
+            *price = (*price as f32 * (1.0 + rise as f32 / 100.0)).max(1.0) as i32;
+            PriceChange::Rise(rise)
+        };
+        events.push(GameEvent::NewsFlash {
+            headline: headline.to_string(),
+            drug,
+            change,
+            price: *price,
+        });
+    }
+}
+
+// Shortages: a drug cannot be had in one city for a few days
+pub struct Shortage;
+
+impl RandomEvent for Shortage {
+    fn name(&self) -> &str {
+        "shortage"
+    }
+
+    // 10% chance of a shortage somewhere each day (by default)
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.shortage
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let city = game.rules.cities.choose(&mut game.rng).unwrap().name;
+        let drug = game.rules.drugs.choose(&mut game.rng).unwrap().name;
+        let days = game.rng.gen_range(2..=5);
+        if let Some(market) = game.markets.get_mut(&city) {
+            market.shortage(drug, days);
+        }
+        events.push(GameEvent::Shortage { drug, city, days });
+    }
+}
+
+// Raids on the stash house: the cops take half of every drug in it (rounded up)
+pub struct StashRaid;
+
+impl RandomEvent for StashRaid {
+    fn name(&self) -> &str {
+        "stash_raid"
+    }
+
+    // Nothing to find in an empty stash house
+    fn is_eligible(&self, game: &Game) -> bool {
+        game.player.stash.values().any(|qty| *qty > 0)
+    }
+
+    // 5% chance of a raid each day (by default)
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.stash_raid
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let mut seized = Vec::new();
+        for drug in game.rules.drug_list() {
+            if let Some(qty) = game.player.stash.get_mut(&drug)
+                && *qty > 0
+            {
+                let taken = (*qty + 1) / 2;
+                *qty -= taken;
+                seized.push((drug, taken));
+            }
+        }
+        game.player.stash.retain(|_, qty| *qty > 0);
+        events.push(GameEvent::StashRaided {
+            city: game.rules.stash_city(),
+            seized,
+        });
+    }
+}
+
+// Muggings: someone takes 10-40% of the cash on hand (the bank is safe)
+pub struct Mugging;
+
+impl RandomEvent for Mugging {
+    fn name(&self) -> &str {
+        "mugging"
+    }
+
+    fn is_eligible(&self, game: &Game) -> bool {
+        game.player.cash > 0
+    }
+
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.mugging
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let share = game.rng.gen_range(10..=40);
+        let amount = (game.player.cash as i64 * share / 100).max(1) as i32;
+        game.player.cash -= amount;
+        events.push(GameEvent::Mugged {
+            amount,
+            cash: game.player.cash,
+        });
+    }
+}
+
+// Finds: a few units of a drug, as many as the trench coat has room for
+pub struct FoundDrugs;
+
+impl RandomEvent for FoundDrugs {
+    fn name(&self) -> &str {
+        "found_drugs"
+    }
+
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.found_drugs
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let info = game.rules.drugs.choose(&mut game.rng).unwrap();
+        let qty = game
+            .rng
+            .gen_range(1..=10)
+            .min(game.player.free_space(&game.rules) / info.space);
+        if qty > 0 {
+            *game.player.inventory.entry(info.name).or_insert(0) += qty;
+            events.push(GameEvent::FoundDrugs {
+                drug: info.name,
+                qty,
+            });
+        }
+    }
+}
+
+// Tips: a friend passes on the going price of a drug in another city
+pub struct Tip;
+
+impl RandomEvent for Tip {
+    fn name(&self) -> &str {
+        "tip"
+    }
+
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.tip
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let here = game.player.city;
+        let elsewhere: Vec<City> = game
+            .rules
+            .city_list()
+            .into_iter()
+            .filter(|city| *city != here)
+            .collect();
+        let Some(&city) = elsewhere.choose(&mut game.rng) else {
+            return;
+        };
+        let drug = game.rules.drugs.choose(&mut game.rng).unwrap().name;
+        if let Some(market) = game.markets.get(&city)
+            && market.is_available(drug)
+        {
+            events.push(GameEvent::Tip {
+                drug,
+                city,
+                price: market.prices[&drug],
+            });
+        }
+    }
+}
+
+// Enforcement of an overdue debt: a warning first, then harsher and harsher ways of getting
+// the money back until the player pays up
+pub struct LoanEnforcement;
+
+impl RandomEvent for LoanEnforcement {
+    fn name(&self) -> &str {
+        "loan_enforcement"
+    }
+
+    fn is_eligible(&self, game: &Game) -> bool {
+        game.player.is_overdue(&game.rules) && game.player.health > 0
+    }
+
+    // Lenders never forget (how rough they get is down to their harshness)
+    fn weight(&self, _game: &Game) -> f64 {
+        1.0
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let lender = game.player.lender;
+        let name = game
+            .rules
+            .lender(lender)
+            .map_or("The loan shark", |info| info.name.as_str())
+            .to_string();
+        let harshness = game.rules.lender_harshness(lender);
+        if game.player.enforcement == 0 || !game.rng.gen_bool(harshness) {
+            game.player.enforcement = game.player.enforcement.max(1);
+            events.push(GameEvent::LoanWarning {
+                lender: name,
+                debt: game.player.debt,
+            });
+            return;
+        }
+        game.player.enforcement += 1;
+        // First they take money, then drugs, then it gets physical
+        let collected = match game.player.enforcement {
+            2 => collect_cash(game, &name, events) || seize_drugs(game, &name, events),
+            3 => seize_drugs(game, &name, events) || collect_cash(game, &name, events),
+            _ => false,
+        };
+        if collected {
+            return;
+        }
+        if game.rng.gen_bool(0.5) {
+            events.push(GameEvent::EncounterStarted {
+                enemy: Enemy::Enforcers,
+                headline: Enemy::Enforcers.headline().to_string(),
+            });
+            game.fight_event(Enemy::Enforcers, events);
+        } else {
+            let damage = game.rng.gen_range(2..=4);
+            game.player.health -= damage;
+            events.push(GameEvent::BonesBroken {
+                lender: name,
+                damage,
+                health: game.player.health,
+            });
+        }
+    }
+}
+
+// The enforcers empty the player's pockets towards the debt. Returns whether there was
+// anything to take.
+fn collect_cash(game: &mut Game, lender: &str, events: &mut Vec<GameEvent>) -> bool {
+    let amount = game.player.cash.min(game.player.debt);
+    if amount <= 0 {
+        return false;
+    }
+    game.player.cash -= amount;
+    game.player.debt -= amount;
+    if game.player.debt == 0 {
+        game.player.enforcement = 0;
+    }
+    events.push(GameEvent::DebtCollected {
+        lender: lender.to_string(),
+        amount,
+        debt: game.player.debt,
+    });
+    true
+}
+
+// The enforcers take everything the player has of one drug. Returns whether there was
+// anything to take.
+fn seize_drugs(game: &mut Game, lender: &str, events: &mut Vec<GameEvent>) -> bool {
+    let owned: Vec<Drug> = game
+        .rules
+        .drug_list()
+        .into_iter()
+        .filter(|drug| game.player.inventory.get(drug).is_some_and(|qty| *qty > 0))
+        .collect();
+    let Some(&drug) = owned.choose(&mut game.rng) else {
+        return false;
+    };
+    let qty = game.player.inventory.insert(drug, 0).unwrap_or(0);
+    events.push(GameEvent::DrugsSeized {
+        lender: lender.to_string(),
+        drug,
+        qty,
+    });
+    true
+}
+
+// Fights: rival drug dealers or cops pick a fight
+pub struct Fight;
+
+impl RandomEvent for Fight {
+    fn name(&self) -> &str {
+        "fight"
+    }
+
+    // Unless the enforcers already picked one (or left the player for dead)
+    fn is_eligible(&self, game: &Game) -> bool {
+        game.encounter.is_none() && game.player.health > 0
+    }
+
+    // 80% chance each day (by default), more or less depending on the city
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.fight * game.here().risk.fight
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let cops_chance = (game.rules.events.cops * game.here().risk.cops).min(1.0);
+        let enemy = if game.rng.gen_bool(cops_chance) {
+            Enemy::Cops
+        } else {
+            Enemy::RivalDealers
+        };
+        events.push(GameEvent::EncounterStarted {
+            enemy,
+            headline: enemy.headline().to_string(),
+        });
+        game.fight_event(enemy, events);
+    }
+}
+
+// Offers: a stranger selling a bigger trench coat or a weapon (cheap, as it fell off a truck)
+pub struct Stranger;
+
+impl RandomEvent for Stranger {
+    fn name(&self) -> &str {
+        "offer"
+    }
+
+    // Strangers keep away from fights
+    fn is_eligible(&self, game: &Game) -> bool {
+        game.encounter.is_none()
+    }
+
+    fn weight(&self, game: &Game) -> f64 {
+        game.rules.events.offer
+    }
+
+    fn apply(&self, game: &mut Game, events: &mut Vec<GameEvent>) {
+        let offer = if game.rng.gen_bool(0.5) {
+            StrangerOffer {
+                item: ShopItem::TrenchCoat,
+                price: game.rng.gen_range(500..=3000),
+            }
+        } else {
+            let weapon = game.rng.gen_range(0..game.rules.weapons.len());
+            let price = game.rules.weapons[weapon].price as f64 * game.rng.gen_range(0.5..=1.0);
+            StrangerOffer {
+                item: ShopItem::Weapon(weapon),
+                price: price.round() as i32,
+            }
+        };
+        game.offer = Some(offer);
+        events.push(GameEvent::OfferMade(offer));
+    }
+}
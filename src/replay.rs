@@ -0,0 +1,175 @@
+// Recording every action of a game and playing it back later.
+// A recording holds the seed plus each action together with what it did at the time, so that
+// replaying it on a fresh game must reach the exact same state. The first step that plays out
+// differently is reported as a `Divergence`, which makes changes to pricing or combat easy to spot.
+use crate::action::{Action, GameEvent, RuleError};
+use crate::game::Game;
+use crate::rules::{Rules, RulesError};
+use crate::save::{Header, SaveError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// Bumped whenever the layout of a replay file changes. Like saves, files of any other version
+// are turned away rather than migrated.
+const REPLAY_VERSION: u32 = 8;
+
+// What an action did: the events it produced, or the reason it was refused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    Events(Vec<GameEvent>),
+    Refused(RuleError),
+}
+
+impl From<&Result<Vec<GameEvent>, RuleError>> for Outcome {
+    fn from(result: &Result<Vec<GameEvent>, RuleError>) -> Self {
+        match result {
+            Ok(events) => Outcome::Events(events.clone()),
+            Err(err) => Outcome::Refused(*err),
+        }
+    }
+}
+
+// One recorded action and what it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub action: Action,
+    pub outcome: Outcome,
+}
+
+// A whole game: the seed and rules it started from and every action taken since
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    version: u32,
+    #[serde(with = "crate::save::wide_number")]
+    pub seed: u64,
+    pub rules: Rules,
+    pub steps: Vec<Step>,
+}
+
+impl Recording {
+    // Starts an empty recording for a game created with `Game::with_rules(seed, rules)`
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        Recording {
+            version: REPLAY_VERSION,
+            seed,
+            rules,
+            steps: Vec::new(),
+        }
+    }
+
+    // Adds an action and the result that `Game::apply` gave for it
+    pub fn record(&mut self, action: Action, result: &Result<Vec<GameEvent>, RuleError>) {
+        self.steps.push(Step {
+            action,
+            outcome: Outcome::from(result),
+        });
+    }
+
+    // Writes the recording to a replay file
+    pub fn save(&self, path: &str) -> Result<(), SaveError> {
+        let content = toml::to_string(self).map_err(SaveError::Write)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    // Reads a recording back from a replay file
+    pub fn load(path: &str) -> Result<Recording, SaveError> {
+        let content = fs::read_to_string(path)?;
+        let header: Header = toml::from_str(&content).map_err(SaveError::Parse)?;
+        if header.version != REPLAY_VERSION {
+            return Err(SaveError::Version(header.version, REPLAY_VERSION));
+        }
+        let recording: Recording = toml::from_str(&content).map_err(SaveError::Parse)?;
+        Ok(recording)
+    }
+
+    // Starts playing the recording back on a fresh game (if the recorded rules are valid)
+    pub fn replay(&self) -> Result<Replayer<'_>, RulesError> {
+        Ok(Replayer {
+            recording: self,
+            game: Game::with_rules(self.seed, self.rules.clone())?,
+            next: 0,
+        })
+    }
+}
+
+// The first recorded step that did not play out the same way again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub step: usize, // Counting from 1
+    pub action: Action,
+    pub expected: Outcome,
+    pub actual: Outcome,
+}
+
+// Plays a recording back one step at a time
+pub struct Replayer<'a> {
+    recording: &'a Recording,
+    game: Game,
+    next: usize,
+}
+
+impl<'a> Replayer<'a> {
+    // The game as it stands after the steps played so far
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // How many steps have been played so far
+    pub fn steps_played(&self) -> usize {
+        self.next
+    }
+
+    // Plays the next recorded step. Returns None once the recording is finished,
+    // or the divergence if the step did not do what it did when it was recorded.
+    pub fn step(&mut self) -> Option<Result<&'a Step, Divergence>> {
+        let step = self.recording.steps.get(self.next)?;
+        self.next += 1;
+        let actual = Outcome::from(&self.game.apply(step.action));
+        if actual != step.outcome {
+            return Some(Err(Divergence {
+                step: self.next,
+                action: step.action,
+                expected: step.outcome.clone(),
+                actual,
+            }));
+        }
+        Some(Ok(step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{RandomStrategy, choose_action};
+
+    #[test]
+    fn recorded_game_replays_identically() {
+        let path = std::env::temp_dir()
+            .join(format!("drugwars_replay_{}.toml", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let seed = u64::MAX;
+        let mut game = Game::with_rules(seed, Rules::default()).unwrap();
+        let mut recording = Recording::new(seed, Rules::default());
+        let mut strategy = RandomStrategy::new(7);
+        for _ in 0..500 {
+            if game.is_game_over() {
+                break;
+            }
+            let action = choose_action(&mut strategy, &game);
+            let result = game.apply(action);
+            recording.record(action, &result);
+        }
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.seed, seed);
+        let mut replayer = loaded.replay().unwrap();
+        while let Some(step) = replayer.step() {
+            assert!(step.is_ok(), "replay diverged: {:?}", step.err());
+        }
+        assert_eq!(replayer.steps_played(), recording.steps.len());
+        assert_eq!(replayer.game().player, game.player);
+    }
+}
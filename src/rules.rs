@@ -0,0 +1,685 @@
+// The rules of the game: starting values, the loan, scoring, the odds of random events,
+// the drugs on the market, the weapons and armor for sale, the henchmen for hire and the map.
+// Defaults come from the constants in lib.rs; a `rules.toml` file can override any of them,
+// so the game can be re-balanced (or made easier for testing) without recompiling.
+use crate::market::{self, City, CityInfo, Drug, DrugInfo, PriceModel};
+use crate::{
+    LOAN_AMOUNT, LOAN_INTEREST, MAX_HEALTH, START_CASH, START_DAYS, START_SPACE, START_WEAPONS,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io};
+
+// Where the game looks for its rules unless told otherwise
+pub const DEFAULT_RULES_FILE: &str = "rules.toml";
+
+// How far the black market's price for a weapon or armor can stray from its usual price
+pub const SHOP_PRICE_SWING: f64 = 0.25;
+
+// Everything about the game that can be tuned from `rules.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    pub start_cash: i32,     // Player's starting cash
+    pub start_space: i32,    // Starting trench coat space (inventory limit)
+    pub start_days: i32,     // Number of days in the game
+    pub loan_interest: f32,  // Daily loan interest rate (of lenders that don't set their own)
+    pub loan_amount: i32,    // Initial loan amount
+    pub max_health: i32,     // Maximum health of the player
+    pub start_weapons: i32,  // Starting number of weapons (of the first kind in the catalogue)
+    pub debt_limit: i32, // Lenders come to collect once debt passes this many times the credit limit
+    pub repayment_days: i32, // Days that lenders wait for a payment before the debt is overdue
+    pub min_payment: f32, // The share of the debt that a payment must cover to buy more time
+    pub score_divisor: f32, // Cash needed for each point of the final score (out of 100)
+    pub bank_interest: f32, // Daily interest paid on money in the bank
+    // The city with the bank (the city that the game starts in when left out)
+    pub bank_city: Option<City>,
+    // The city with the player's stash house (the city that the game starts in when left out)
+    pub stash_city: Option<City>,
+    pub stash_space: i32, // Drugs that the stash house holds to begin with
+    pub stash_upgrade_space: i32, // Extra space that each upgrade of the stash house adds
+    pub stash_upgrade_cost: i32, // The price of each upgrade
+    pub ammo_box: i32,    // Rounds of ammunition in each box that the black market sells
+    pub ammo_per_space: i32, // Rounds of ammunition that fit in one unit of trench coat space
+    // Days in jail after surrendering to the cops (twice as many for being caught fighting them)
+    pub jail_days: i32,
+    // The share of the cash on hand that the cops confiscate on an arrest
+    pub confiscation: f32,
+    pub bribe_cost: i32, // The bribe that each cop wants from a player with no heat on them
+    pub bribe_heat: f32, // How much each point of heat adds to a bribe (0.5 for half as much again)
+    // The share of each drug carried that is dropped to distract rival dealers
+    pub drop_share: f32,
+    pub events: EventOdds,       // The chances of random events
+    pub henchmen: HenchmanTerms, // What henchmen cost and how well they fight
+    // How prices move from day to day: "random-walk" (the default) or "classic"
+    pub price_model: PriceModel,
+    // The drug catalogue, in the order that the market lists it (src/drugs.toml by default)
+    pub drugs: Vec<DrugInfo>,
+    // The map, starting from the first city (src/cities.toml by default)
+    pub cities: Vec<CityInfo>,
+    // The people who lend money; the first one made the starting loan
+    pub lenders: Vec<Lender>,
+    // The weapon catalogue, in the order that the black market lists it
+    pub weapons: Vec<WeaponInfo>,
+    // The armor catalogue, in the order that the black market lists it
+    pub armor: Vec<ArmorInfo>,
+}
+
+// Someone who lends money, and how they go about it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lender {
+    pub name: String, // The name shown to the player
+    // Daily interest rate (loan_interest when left out)
+    #[serde(default)]
+    pub interest: Option<f32>,
+    // How many times the credit limit the debt may grow to before they come to collect
+    // (debt_limit when left out, so lower is harsher)
+    #[serde(default)]
+    pub debt_limit: Option<i32>,
+    // The chance that they get rough over an overdue debt rather than sending another
+    // warning, each day (events.enforcement when left out)
+    #[serde(default)]
+    pub harshness: Option<f64>,
+}
+
+// A weapon that the black market sells, and how it does in a fight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponInfo {
+    pub name: String,    // The name shown to the player
+    pub min_damage: i32, // The least damage that a hit does
+    pub max_damage: i32, // The most damage that a hit does
+    pub accuracy: f64,   // The chance of hitting with each attack
+    pub price: i32,      // The usual black market price (the day's price is within 25% of it)
+    // Rounds of ammunition that each attack uses up (for firearms)
+    #[serde(default)]
+    pub ammo: i32,
+    // Attacks that it lasts before it breaks (for melee weapons; it never does when left out)
+    #[serde(default)]
+    pub durability: Option<i32>,
+}
+
+impl WeaponInfo {
+    // The damage that an attack does on average, misses included
+    pub fn expected_damage(&self) -> f64 {
+        self.accuracy * (self.min_damage + self.max_damage) as f64 / 2.0
+    }
+    // The most that the black market ever asks for it
+    pub fn highest_price(&self) -> i32 {
+        (self.price as f64 * (1.0 + SHOP_PRICE_SWING)).round() as i32
+    }
+}
+
+// Body armor that the black market sells
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArmorInfo {
+    pub name: String,    // The name shown to the player
+    pub absorb: f64,     // The share of the damage from each hit that it soaks up
+    pub durability: i32, // The damage that it soaks up in all before it falls apart
+    pub price: i32,      // The usual black market price (the day's price is within 25% of it)
+}
+
+// What it takes to keep a crew of henchmen, and what they are good for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HenchmanTerms {
+    pub fee: i32,        // What it costs to hire a henchman
+    pub wage: i32,       // What each henchman is paid every day
+    pub max: i32,        // The most henchmen that the player can have at once
+    pub health: i32,     // The health of each henchman (they are patched up overnight)
+    pub min_damage: i32, // The least damage that a henchman's hit deals
+    pub max_damage: i32, // The most damage that a henchman's hit deals
+    pub accuracy: f64,   // The chance that a henchman's attack hits
+    pub desert: f64,     // The chance that a henchman walks off on a day that they go unpaid
+}
+
+// The chances (from 0.0 to 1.0) of the random events in the game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventOdds {
+    pub news_flash: f64,  // A news flash moves a drug price, each day
+    pub fight: f64,       // Cops or rival dealers pick a fight, each day
+    pub cops: f64,        // A fight is with the cops rather than rival dealers
+    pub taunt: f64,       // Someone shouts something funny, each round of a fight
+    pub escape: f64,      // Running away from a fight works
+    pub hide: f64,        // Hiding from a fight works (times the city's hide risk)
+    pub bribe: f64,       // The cops take a bribe
+    pub distract: f64,    // Rival dealers go for the drugs dropped rather than the player
+    pub sold_out: f64,    // A drug is not to be had in a city, for each drug and city each day
+    pub shortage: f64,    // The news reports a shortage that lasts a few days, each day
+    pub enforcement: f64, // A lender gets rough over an overdue debt, each day after a warning
+    pub stash_raid: f64,  // The cops raid the stash house while there is anything in it, each day
+    pub mugging: f64,     // Someone takes a share of the cash on hand, on the way into town
+    pub found_drugs: f64, // Drugs are lying around for the taking, on the way into town
+    pub offer: f64,       // A stranger offers a bigger coat or a gun, on the way into town
+    pub tip: f64,         // A friend tips the player off about prices elsewhere, each day
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            start_cash: START_CASH,
+            start_space: START_SPACE,
+            start_days: START_DAYS,
+            loan_interest: LOAN_INTEREST,
+            loan_amount: LOAN_AMOUNT,
+            max_health: MAX_HEALTH,
+            start_weapons: START_WEAPONS,
+            debt_limit: 2,
+            repayment_days: 7,
+            min_payment: 0.1,
+            score_divisor: 1_000_000.0 / 2.0,
+            bank_interest: 0.01,
+            bank_city: None,
+            stash_city: None,
+            stash_space: 100,
+            stash_upgrade_space: 100,
+            stash_upgrade_cost: 5000,
+            ammo_box: 10,
+            ammo_per_space: 5,
+            jail_days: 3,
+            confiscation: 0.5,
+            bribe_cost: 1000,
+            bribe_heat: 0.5,
+            drop_share: 0.5,
+            events: EventOdds::default(),
+            henchmen: HenchmanTerms::default(),
+            price_model: PriceModel::default(),
+            drugs: market::standard_drugs(),
+            cities: market::standard_cities(),
+            lenders: standard_lenders(),
+            weapons: standard_weapons(),
+            armor: standard_armor(),
+        }
+    }
+}
+
+// The armor that the game ships with, from the cheapest up
+fn standard_armor() -> Vec<ArmorInfo> {
+    vec![
+        ArmorInfo {
+            name: "Leather Jacket".to_string(),
+            absorb: 0.25,
+            durability: 6,
+            price: 1000,
+        },
+        ArmorInfo {
+            name: "Kevlar Vest".to_string(),
+            absorb: 0.5,
+            durability: 15,
+            price: 4000,
+        },
+        ArmorInfo {
+            name: "Riot Gear".to_string(),
+            absorb: 0.75,
+            durability: 30,
+            price: 10000,
+        },
+    ]
+}
+
+// The weapons that the game ships with, from the cheapest up
+fn standard_weapons() -> Vec<WeaponInfo> {
+    vec![
+        WeaponInfo {
+            name: "Baseball Bat".to_string(),
+            min_damage: 2,
+            max_damage: 4,
+            accuracy: 0.9,
+            price: 500,
+            ammo: 0,
+            durability: Some(12),
+        },
+        WeaponInfo {
+            name: "Pistol".to_string(),
+            min_damage: 3,
+            max_damage: 7,
+            accuracy: 0.8,
+            price: 2500,
+            ammo: 1,
+            durability: None,
+        },
+        WeaponInfo {
+            name: "Shotgun".to_string(),
+            min_damage: 6,
+            max_damage: 10,
+            accuracy: 0.6,
+            price: 5000,
+            ammo: 1,
+            durability: None,
+        },
+        WeaponInfo {
+            name: "Assault Rifle".to_string(),
+            min_damage: 5,
+            max_damage: 9,
+            accuracy: 0.85,
+            price: 9000,
+            ammo: 3,
+            durability: None,
+        },
+    ]
+}
+
+// The lenders that the game ships with
+fn standard_lenders() -> Vec<Lender> {
+    vec![
+        Lender {
+            name: "The Loan Shark".to_string(),
+            interest: None,
+            debt_limit: None,
+            harshness: None,
+        },
+        // Cheap, but comes for you as soon as you owe more than your credit limit
+        Lender {
+            name: "Uncle Vinnie".to_string(),
+            interest: Some(0.08),
+            debt_limit: Some(1),
+            harshness: Some(0.9),
+        },
+        // Pricey, but lets the debt run a long way and rarely gets rough
+        Lender {
+            name: "Mama Rosa".to_string(),
+            interest: Some(0.25),
+            debt_limit: Some(4),
+            harshness: Some(0.2),
+        },
+    ]
+}
+
+impl Default for HenchmanTerms {
+    fn default() -> Self {
+        HenchmanTerms {
+            fee: 1000,
+            wage: 200,
+            max: 4,
+            health: 6,
+            min_damage: 2,
+            max_damage: 4,
+            accuracy: 0.7,
+            desert: 0.5,
+        }
+    }
+}
+
+impl Default for EventOdds {
+    fn default() -> Self {
+        EventOdds {
+            news_flash: 0.3,
+            fight: 0.8,
+            cops: 0.5,
+            taunt: 0.5,
+            escape: 0.5,
+            hide: 0.4,
+            bribe: 0.75,
+            distract: 0.7,
+            sold_out: 0.1,
+            shortage: 0.1,
+            enforcement: 0.5,
+            stash_raid: 0.05,
+            mugging: 0.05,
+            found_drugs: 0.05,
+            offer: 0.1,
+            tip: 0.1,
+        }
+    }
+}
+
+// The ways that reading the rules can go wrong
+#[derive(Debug)]
+pub enum RulesError {
+    Io(io::Error),          // The file could not be read
+    Parse(toml::de::Error), // The file is not valid TOML, or has unknown settings
+    Invalid(String),        // A setting is out of range
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Io(err) => write!(f, "Could not read the rules file: {}", err),
+            RulesError::Parse(err) => write!(f, "The rules file is not valid: {}", err),
+            RulesError::Invalid(problem) => write!(f, "The rules file is not valid: {}", problem),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl Rules {
+    // Reads the rules from a TOML file. Settings that are left out keep their defaults.
+    pub fn load(path: &str) -> Result<Rules, RulesError> {
+        let content = fs::read_to_string(path).map_err(RulesError::Io)?;
+        let rules: Rules = toml::from_str(&content).map_err(RulesError::Parse)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    // Reads the rules from a TOML file, or uses the defaults if there is no such file
+    pub fn load_or_default(path: &str) -> Result<Rules, RulesError> {
+        if std::path::Path::new(path).exists() {
+            Rules::load(path)
+        } else {
+            Ok(Rules::default())
+        }
+    }
+
+    // Checks that every setting makes sense
+    pub fn validate(&self) -> Result<(), RulesError> {
+        let invalid = |problem: &str| Err(RulesError::Invalid(problem.to_string()));
+        if self.start_cash < 0 || self.loan_amount < 0 || self.start_weapons < 0 {
+            return invalid("start_cash, loan_amount and start_weapons cannot be negative");
+        }
+        if self.start_space <= 0 || self.start_days <= 0 || self.max_health <= 0 {
+            return invalid("start_space, start_days and max_health must be above zero");
+        }
+        if self.loan_interest < 0.0 || self.debt_limit <= 0 || self.score_divisor <= 0.0 {
+            return invalid("loan_interest, debt_limit and score_divisor must be positive");
+        }
+        if self.bank_interest < 0.0 {
+            return invalid("bank_interest cannot be negative");
+        }
+        if self.stash_space < 0 || self.stash_upgrade_space < 0 || self.stash_upgrade_cost < 0 {
+            return invalid(
+                "stash_space, stash_upgrade_space and stash_upgrade_cost cannot be negative",
+            );
+        }
+        if self.ammo_box <= 0 || self.ammo_per_space <= 0 {
+            return invalid("ammo_box and ammo_per_space must be above zero");
+        }
+        if self.jail_days < 0 || !(0.0..=1.0).contains(&self.confiscation) {
+            return invalid(
+                "jail_days cannot be negative and confiscation must be from 0.0 to 1.0",
+            );
+        }
+        if self.bribe_cost < 0 || self.bribe_heat < 0.0 {
+            return invalid("bribe_cost and bribe_heat cannot be negative");
+        }
+        if !(self.drop_share > 0.0 && self.drop_share <= 1.0) {
+            return invalid("drop_share must be above 0.0 and at most 1.0");
+        }
+        if self.repayment_days <= 0 || !(0.0..=1.0).contains(&self.min_payment) {
+            return invalid("repayment_days must be above zero and min_payment from 0.0 to 1.0");
+        }
+        let odds = &self.events;
+        let chances = [
+            ("news_flash", odds.news_flash),
+            ("fight", odds.fight),
+            ("cops", odds.cops),
+            ("taunt", odds.taunt),
+            ("escape", odds.escape),
+            ("hide", odds.hide),
+            ("bribe", odds.bribe),
+            ("distract", odds.distract),
+            ("sold_out", odds.sold_out),
+            ("shortage", odds.shortage),
+            ("enforcement", odds.enforcement),
+            ("stash_raid", odds.stash_raid),
+            ("mugging", odds.mugging),
+            ("found_drugs", odds.found_drugs),
+            ("offer", odds.offer),
+            ("tip", odds.tip),
+        ];
+        for (name, chance) in chances {
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(RulesError::Invalid(format!(
+                    "events.{} must be between 0.0 and 1.0",
+                    name
+                )));
+            }
+        }
+        let crew = &self.henchmen;
+        if crew.fee < 0
+            || crew.wage < 0
+            || crew.max < 0
+            || crew.health <= 0
+            || crew.min_damage <= 0
+            || crew.max_damage < crew.min_damage
+        {
+            return invalid(
+                "henchmen need a positive health and damage, and fee, wage and max cannot be negative",
+            );
+        }
+        if !(crew.accuracy > 0.0 && crew.accuracy <= 1.0 && (0.0..=1.0).contains(&crew.desert)) {
+            return invalid(
+                "henchmen.accuracy must be above 0.0 and henchmen.desert from 0.0 to 1.0",
+            );
+        }
+        if self.drugs.is_empty() {
+            return invalid("there must be at least one drug on the market");
+        }
+        for (i, info) in self.drugs.iter().enumerate() {
+            info.validate().map_err(RulesError::Invalid)?;
+            if self.drugs[..i].iter().any(|other| other.name == info.name) {
+                return Err(RulesError::Invalid(format!(
+                    "{} is listed more than once",
+                    info.name.name()
+                )));
+            }
+        }
+        if self.cities.is_empty() {
+            return invalid("there must be at least one city on the map");
+        }
+        for (i, info) in self.cities.iter().enumerate() {
+            info.validate().map_err(RulesError::Invalid)?;
+            let name = info.name.name();
+            if self.cities[..i].iter().any(|other| other.name == info.name) {
+                return Err(RulesError::Invalid(format!(
+                    "{} is listed more than once",
+                    name
+                )));
+            }
+            if let Some(route) = info.routes.iter().find(|r| self.city_info(r.to).is_none()) {
+                return Err(RulesError::Invalid(format!(
+                    "{} has a route to {}, which is not on the map",
+                    name,
+                    route.to.name()
+                )));
+            }
+            if let Some(drug) = info
+                .price_multipliers
+                .keys()
+                .find(|drug| self.drug_info(**drug).is_none())
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} has a price multiplier for {}, which is not on the market",
+                    name,
+                    drug.name()
+                )));
+            }
+        }
+        if self.lenders.is_empty() {
+            return invalid("there must be at least one lender");
+        }
+        for (i, lender) in self.lenders.iter().enumerate() {
+            if lender.name.is_empty() {
+                return invalid("every lender needs a name");
+            }
+            if self.lenders[..i]
+                .iter()
+                .any(|other| other.name == lender.name)
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} is listed more than once",
+                    lender.name
+                )));
+            }
+            if lender.interest.is_some_and(|rate| rate < 0.0)
+                || lender.debt_limit.is_some_and(|limit| limit <= 0)
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} must have a positive interest and debt_limit",
+                    lender.name
+                )));
+            }
+            if lender
+                .harshness
+                .is_some_and(|chance| !(0.0..=1.0).contains(&chance))
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} must have a harshness between 0.0 and 1.0",
+                    lender.name
+                )));
+            }
+        }
+        if self.weapons.is_empty() {
+            return invalid("there must be at least one weapon");
+        }
+        for (i, weapon) in self.weapons.iter().enumerate() {
+            if weapon.name.is_empty() {
+                return invalid("every weapon needs a name");
+            }
+            if self.weapons[..i]
+                .iter()
+                .any(|other| other.name == weapon.name)
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} is listed more than once",
+                    weapon.name
+                )));
+            }
+            if weapon.min_damage <= 0
+                || weapon.max_damage < weapon.min_damage
+                || weapon.price < 0
+                || !(weapon.accuracy > 0.0 && weapon.accuracy <= 1.0)
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} needs a damage range above zero, an accuracy above 0.0 and up to 1.0 \
+                     and a price that is not negative",
+                    weapon.name
+                )));
+            }
+            if weapon.ammo < 0 || weapon.durability.is_some_and(|attacks| attacks <= 0) {
+                return Err(RulesError::Invalid(format!(
+                    "{} cannot use negative ammo and its durability must be above zero",
+                    weapon.name
+                )));
+            }
+        }
+        for (i, armor) in self.armor.iter().enumerate() {
+            if armor.name.is_empty() {
+                return invalid("every piece of armor needs a name");
+            }
+            if self.armor[..i].iter().any(|other| other.name == armor.name) {
+                return Err(RulesError::Invalid(format!(
+                    "{} is listed more than once",
+                    armor.name
+                )));
+            }
+            if !(armor.absorb > 0.0 && armor.absorb <= 1.0)
+                || armor.durability <= 0
+                || armor.price < 0
+            {
+                return Err(RulesError::Invalid(format!(
+                    "{} needs an absorb above 0.0 and up to 1.0, a durability above zero and a \
+                     price that is not negative",
+                    armor.name
+                )));
+            }
+        }
+        if let Some(city) = self.bank_city
+            && self.city_info(city).is_none()
+        {
+            return Err(RulesError::Invalid(format!(
+                "bank_city {} is not on the map",
+                city.name()
+            )));
+        }
+        if let Some(city) = self.stash_city
+            && self.city_info(city).is_none()
+        {
+            return Err(RulesError::Invalid(format!(
+                "stash_city {} is not on the map",
+                city.name()
+            )));
+        }
+        Ok(())
+    }
+
+    // The drugs on the market, in catalogue order
+    pub fn drug_list(&self) -> Vec<Drug> {
+        self.drugs.iter().map(|info| info.name).collect()
+    }
+
+    // The catalogue entry for a drug, if it is on the market at all
+    pub fn drug_info(&self, drug: Drug) -> Option<&DrugInfo> {
+        self.drugs.iter().find(|info| info.name == drug)
+    }
+
+    // The cities on the map, in the order that they are listed
+    pub fn city_list(&self) -> Vec<City> {
+        self.cities.iter().map(|info| info.name).collect()
+    }
+
+    // The map entry for a city, if it is on the map at all
+    pub fn city_info(&self, city: City) -> Option<&CityInfo> {
+        self.cities.iter().find(|info| info.name == city)
+    }
+
+    // The city that every game starts in
+    pub fn start_city(&self) -> City {
+        self.cities[0].name
+    }
+
+    // The city with the bank
+    pub fn bank_city(&self) -> City {
+        self.bank_city.unwrap_or_else(|| self.start_city())
+    }
+
+    // The city with the player's stash house
+    pub fn stash_city(&self) -> City {
+        self.stash_city.unwrap_or_else(|| self.start_city())
+    }
+
+    // The lender with this number (in the order that they are listed), if there is one
+    pub fn lender(&self, lender: usize) -> Option<&Lender> {
+        self.lenders.get(lender)
+    }
+
+    // The weapon with this number (in catalogue order), if there is one
+    pub fn weapon(&self, weapon: usize) -> Option<&WeaponInfo> {
+        self.weapons.get(weapon)
+    }
+
+    // The armor with this number (in catalogue order), if there is one
+    pub fn armor(&self, armor: usize) -> Option<&ArmorInfo> {
+        self.armor.get(armor)
+    }
+
+    // The trench coat space that some rounds of ammunition take up
+    pub fn ammo_space(&self, rounds: i32) -> i32 {
+        (rounds.max(0) + self.ammo_per_space - 1) / self.ammo_per_space
+    }
+
+    // What a number of cops want to look the other way, given the player's heat
+    pub fn bribe_price(&self, cops: i32, heat: i32) -> i32 {
+        (self.bribe_cost as f32 * cops as f32 * (1.0 + self.bribe_heat * heat as f32)).round()
+            as i32
+    }
+
+    // The chance of hiding from a fight in a city
+    pub fn hide_chance(&self, city: City) -> f64 {
+        let risk = self.city_info(city).map_or(1.0, |info| info.risk.hide);
+        (self.events.hide * risk).min(1.0)
+    }
+
+    // The daily interest rate charged by a lender
+    pub fn lender_interest(&self, lender: usize) -> f32 {
+        self.lender(lender)
+            .and_then(|info| info.interest)
+            .unwrap_or(self.loan_interest)
+    }
+
+    // How many times the credit limit a lender lets the debt grow to before coming to collect
+    pub fn lender_debt_limit(&self, lender: usize) -> i32 {
+        self.lender(lender)
+            .and_then(|info| info.debt_limit)
+            .unwrap_or(self.debt_limit)
+    }
+
+    // The chance that a lender gets rough over an overdue debt, each day after a warning
+    pub fn lender_harshness(&self, lender: usize) -> f64 {
+        self.lender(lender)
+            .and_then(|info| info.harshness)
+            .unwrap_or(self.events.enforcement)
+    }
+}
@@ -0,0 +1,288 @@
+// Saving a game to disk and picking it up again later.
+// Save files are plain TOML so that they can be read (and, for the brave, edited) by hand.
+use crate::action::{ShopOffer, StrangerOffer};
+use crate::game::{Encounter, Game};
+use crate::market::{City, Market};
+use crate::player::Player;
+use crate::rules::Rules;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::{fmt, fs, io};
+
+// Bumped whenever the layout of a save file changes. Files of any other version are turned
+// away with a clear message instead of being migrated.
+const SAVE_VERSION: u32 = 7;
+
+// Everything needed to carry on exactly where the player left off
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    // The seed that the game was started from
+    #[serde(with = "wide_number")]
+    seed: u64,
+    // The rules that the game is played by
+    rules: Rules,
+    // How far along its stream the random number generator is, so the game stays reproducible
+    #[serde(with = "wide_number")]
+    rng_word_pos: u64,
+    player: Player,
+    markets: HashMap<City, Market>,
+    encounter: Option<Encounter>,
+    shop: Option<ShopOffer>,
+    // A stranger's offer that the player has yet to answer
+    offer: Option<StrangerOffer>,
+}
+
+// TOML integers stop at i64::MAX, so seeds (which can be any u64) are written out as strings.
+// Plain numbers, as someone editing a save by hand might write them, are still read.
+pub(crate) mod wide_number {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Wide {
+            Number(u64),
+            Text(String),
+        }
+        match Wide::deserialize(deserializer)? {
+            Wide::Number(value) => Ok(value),
+            Wide::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// Just the version of a save or replay file. It is read before anything else, so that a file
+// from another version of the game is reported as such rather than as a damaged file.
+#[derive(Deserialize)]
+pub(crate) struct Header {
+    pub version: u32,
+}
+
+// The ways that saving or loading can go wrong
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),           // The file could not be read or written
+    Parse(toml::de::Error),  // The file is not a valid save file
+    Write(toml::ser::Error), // The game could not be written out
+    Version(u32, u32),       // The file has another version (first) than this game reads (second)
+    Rules(String),           // The rules stored in the file are not valid
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "Could not access the save file: {}", err),
+            SaveError::Parse(err) => write!(f, "The save file is damaged: {}", err),
+            SaveError::Write(err) => write!(f, "Could not write the save file: {}", err),
+            SaveError::Version(version, expected) => write!(
+                f,
+                "The file has version {} but this game reads version {}",
+                version, expected
+            ),
+            SaveError::Rules(problem) => write!(f, "The saved rules are not valid: {}", problem),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl Game {
+    // Writes the whole game, including the random number generator, to a save file
+    pub fn save(&self, path: &str) -> Result<(), SaveError> {
+        let save = SaveFile {
+            version: SAVE_VERSION,
+            seed: self.seed,
+            rules: self.rules.clone(),
+            rng_word_pos: self.rng.get_word_pos() as u64,
+            player: self.player.clone(),
+            markets: self.markets.clone(),
+            encounter: self.encounter.clone(),
+            shop: self.shop.clone(),
+            offer: self.offer,
+        };
+        let content = toml::to_string(&save).map_err(SaveError::Write)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    // Reads a game back from a save file, ready to carry on exactly where it stopped
+    pub fn load(path: &str) -> Result<Game, SaveError> {
+        let content = fs::read_to_string(path)?;
+        let header: Header = toml::from_str(&content).map_err(SaveError::Parse)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::Version(header.version, SAVE_VERSION));
+        }
+        let mut save: SaveFile = toml::from_str(&content).map_err(SaveError::Parse)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(save.seed);
+        rng.set_word_pos(save.rng_word_pos as u128);
+        save.rules
+            .validate()
+            .map_err(|err| SaveError::Rules(err.to_string()))?;
+        if save.rules.city_info(save.player.city).is_none() {
+            return Err(SaveError::Rules(format!(
+                "{} is not on the map",
+                save.player.city.name()
+            )));
+        }
+        if save.rules.lender(save.player.lender).is_none() {
+            return Err(SaveError::Rules(format!(
+                "there is no lender number {}",
+                save.player.lender
+            )));
+        }
+        if let Some(weapon) = save
+            .player
+            .weapons
+            .iter()
+            .find(|weapon| save.rules.weapon(weapon.kind).is_none())
+        {
+            return Err(SaveError::Rules(format!(
+                "there is no weapon number {}",
+                weapon.kind
+            )));
+        }
+        if let Some(armor) = save
+            .player
+            .armor
+            .filter(|armor| save.rules.armor(armor.kind).is_none())
+        {
+            return Err(SaveError::Rules(format!(
+                "there is no armor number {}",
+                armor.kind
+            )));
+        }
+        if let Some(city) = save
+            .rules
+            .cities
+            .iter()
+            .find(|city| !save.markets.contains_key(&city.name))
+        {
+            return Err(SaveError::Rules(format!(
+                "there is no market in {}",
+                city.name.name()
+            )));
+        }
+        // Every market needs a price for every drug, which there is no making up
+        for city in &save.rules.cities {
+            if let Some(info) = save
+                .rules
+                .drugs
+                .iter()
+                .find(|info| !save.markets[&city.name].prices.contains_key(&info.name))
+            {
+                return Err(SaveError::Rules(format!(
+                    "the market in {} has no price for {}",
+                    city.name.name(),
+                    info.name.name()
+                )));
+            }
+        }
+        // A drug left out of the trench coat is simply not being carried
+        for drug in save.rules.drug_list() {
+            save.player.inventory.entry(drug).or_insert(0);
+        }
+        let mut game = Game::with_rules(save.seed, save.rules)
+            .map_err(|err| SaveError::Rules(err.to_string()))?;
+        game.rng = rng;
+        game.player = save.player;
+        game.markets = save.markets;
+        game.encounter = save.encounter;
+        game.shop = save.shop;
+        game.offer = save.offer;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::market::Drug;
+    use crate::player::Armor;
+
+    // A save file in the system's temporary directory, unique to this test run
+    fn temp_file(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("drugwars_{}_{}.toml", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn save_and_load_round_trip_any_seed() {
+        let path = temp_file("save_round_trip");
+        for seed in [0, 42, i64::MAX as u64 + 1, u64::MAX] {
+            let mut game = Game::with_seed(seed);
+            let city = game.routes()[0].to;
+            game.apply(Action::Travel(city)).unwrap();
+            game.save(&path).unwrap();
+            let mut loaded = Game::load(&path).unwrap();
+            assert_eq!(loaded.seed(), seed);
+            assert_eq!(loaded.player, game.player);
+            // Play carries on exactly as if the game had never been saved
+            for _ in 0..5 {
+                let city = game.routes()[0].to;
+                assert_eq!(
+                    loaded.apply(Action::Travel(city)),
+                    game.apply(Action::Travel(city))
+                );
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_turns_away_armor_missing_from_the_rules() {
+        let path = temp_file("save_unknown_armor");
+        let mut game = Game::with_seed(1);
+        game.player.armor = Some(Armor {
+            kind: game.rules().armor.len(),
+            wear: 0,
+        });
+        game.save(&path).unwrap();
+        assert!(matches!(Game::load(&path), Err(SaveError::Rules(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_turns_away_markets_missing_a_price() {
+        let path = temp_file("save_missing_price");
+        let mut game = Game::with_seed(1);
+        let weed = Drug::named("Weed");
+        let city = game.player.city;
+        game.markets.get_mut(&city).unwrap().prices.remove(&weed);
+        game.save(&path).unwrap();
+        assert!(matches!(Game::load(&path), Err(SaveError::Rules(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_fills_in_drugs_missing_from_the_inventory() {
+        let path = temp_file("save_missing_drug");
+        let mut game = Game::with_seed(1);
+        let weed = Drug::named("Weed");
+        game.player.inventory.remove(&weed);
+        game.save(&path).unwrap();
+        let mut loaded = Game::load(&path).unwrap();
+        assert_eq!(loaded.player.inventory.get(&weed), Some(&0));
+        // Buying and selling it carries on as normal
+        if loaded.max_buyable(weed) > 0 {
+            loaded.apply(Action::Buy { drug: weed, qty: 1 }).unwrap();
+            loaded.apply(Action::Sell { drug: weed, qty: 1 }).unwrap();
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+// Headless batch simulation for balancing the game.
+// Plays many games with a strategy and no terminal in sight, then sums up how they went so that
+// price ranges, fight odds and the loan terms can be tuned with data instead of by feel.
+use crate::action::{Enemy, GameEvent};
+use crate::game::Game;
+use crate::rules::{Rules, RulesError};
+use crate::strategy::{Strategy, choose_action};
+
+// Gives up on a game after this many actions, in case a strategy never moves the day along
+const MAX_ACTIONS_PER_GAME: usize = 10_000;
+
+// How a simulated game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    TimeUp,  // Survived all of the days
+    Died,    // Health ran out
+    DebtOut, // The lender's enforcers finished the player off
+    Stalled, // The strategy stopped making progress
+}
+
+// The result of one simulated game
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSummary {
+    pub seed: u64,
+    pub final_cash: i32, // Cash on hand plus the bank
+    pub final_debt: i32,
+    pub score: i32,
+    pub days: i32, // How many days the game lasted
+    pub ending: Ending,
+    pub refused_actions: usize, // Actions that the rules turned down
+}
+
+// Plays one game from `seed` to the end by the given rules with the given strategy
+pub fn run_game(
+    seed: u64,
+    rules: &Rules,
+    strategy: &mut dyn Strategy,
+) -> Result<GameSummary, RulesError> {
+    let mut game = Game::with_rules(seed, rules.clone())?;
+    let mut refused_actions = 0;
+    let mut actions = 0;
+    let mut hurt_by_lender = false;
+    while !game.is_game_over() && actions < MAX_ACTIONS_PER_GAME {
+        let action = choose_action(strategy, &game);
+        match game.apply(action) {
+            Ok(events) => {
+                hurt_by_lender = events.iter().any(|event| {
+                    matches!(
+                        event,
+                        GameEvent::BonesBroken { .. }
+                            | GameEvent::PlayerDefeated {
+                                enemy: Enemy::Enforcers
+                            }
+                    )
+                })
+            }
+            Err(_) => refused_actions += 1,
+        }
+        actions += 1;
+    }
+    let ending = if game.player.health <= 0 && hurt_by_lender {
+        Ending::DebtOut
+    } else if game.player.health <= 0 {
+        Ending::Died
+    } else if game.player.day > rules.start_days {
+        Ending::TimeUp
+    } else {
+        Ending::Stalled
+    };
+    Ok(GameSummary {
+        seed,
+        final_cash: game.player.total_cash(),
+        final_debt: game.player.debt,
+        score: game.final_score(),
+        days: game.player.day.min(rules.start_days),
+        ending,
+        refused_actions,
+    })
+}
+
+// Summary statistics for one measurement across all of the games
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub mean: f64,
+    pub min: f64,
+    pub p10: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    // Sums up a list of values (all zeros when the list is empty)
+    pub fn from_values(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            values.push(0.0);
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0) * values.len() as f64).ceil() as usize;
+            values[rank.clamp(1, values.len()) - 1]
+        };
+        Distribution {
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            min: values[0],
+            p10: percentile(10.0),
+            p25: percentile(25.0),
+            median: percentile(50.0),
+            p75: percentile(75.0),
+            p90: percentile(90.0),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+// Everything learned from a batch of games
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub strategy: String,
+    pub games: Vec<GameSummary>,
+    pub final_cash: Distribution,
+    pub score: Distribution,
+    pub days: Distribution,
+    pub death_rate: f64,    // Share of games that ended with the player dead
+    pub debt_out_rate: f64, // Share of games ended by a lender's enforcers
+    pub stalled_rate: f64,  // Share of games that the strategy could not finish
+}
+
+impl SimulationReport {
+    // Sums up a batch of finished games
+    pub fn new(strategy: &str, games: Vec<GameSummary>) -> Self {
+        let rate = |ending: Ending| {
+            let count = games.iter().filter(|game| game.ending == ending).count();
+            count as f64 / games.len().max(1) as f64
+        };
+        let values = |field: fn(&GameSummary) -> i32| {
+            Distribution::from_values(games.iter().map(|game| field(game) as f64).collect())
+        };
+        SimulationReport {
+            strategy: strategy.to_string(),
+            final_cash: values(|game| game.final_cash),
+            score: values(|game| game.score),
+            days: values(|game| game.days),
+            death_rate: rate(Ending::Died),
+            debt_out_rate: rate(Ending::DebtOut),
+            stalled_rate: rate(Ending::Stalled),
+            games,
+        }
+    }
+}
+
+// Plays `count` games with seeds `first_seed`, `first_seed + 1`, ... and reports on them.
+// `make_strategy` builds a fresh strategy for each game from that game's seed.
+pub fn simulate(
+    count: usize,
+    first_seed: u64,
+    rules: &Rules,
+    mut make_strategy: impl FnMut(u64) -> Box<dyn Strategy>,
+) -> Result<SimulationReport, RulesError> {
+    let mut name = String::new();
+    let games = (0..count as u64)
+        .map(|i| {
+            let seed = first_seed.wrapping_add(i);
+            let mut strategy = make_strategy(seed);
+            name = strategy.name().to_string();
+            run_game(seed, rules, strategy.as_mut())
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(SimulationReport::new(&name, games))
+}
@@ -0,0 +1,479 @@
+// Computer players. A strategy is shown what a player at the terminal would see (an
+// `Observation`) and picks the next action, so anything that a strategy does goes through the
+// same rules as a human.
+use crate::action::{Action, Enemy, ShopItem, ShopOffer, StrangerOffer};
+use crate::game::{AMMO_PRICES, COAT_PRICES, Encounter, Game, MEDICINE_PRICES};
+use crate::market::{Drug, Market, Route};
+use crate::player::Player;
+use crate::rules::Rules;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+// What the player can see of the game when deciding what to do next
+pub struct Observation<'a> {
+    // Cash, debt, health, inventory, weapons, day and city
+    pub player: &'a Player,
+    // Today's prices in the current city
+    pub prices: &'a HashMap<Drug, i32>,
+    // The whole market in the current city, including what is sold out
+    pub market: &'a Market,
+    // Days remaining after today
+    pub days_left: i32,
+    // The rules of the game
+    pub rules: &'a Rules,
+    // The fight in progress, if any
+    pub encounter: Option<&'a Encounter>,
+    // The black market offers, while visiting
+    pub shop: Option<&'a ShopOffer>,
+    // A stranger's offer, until the player does anything else
+    pub offer: Option<&'a StrangerOffer>,
+    // The game itself, which answers the questions below the same way that it applies the rules
+    game: &'a Game,
+}
+
+impl Observation<'_> {
+    // The most units of a drug that the player can afford and carry
+    pub fn max_buyable(&self, drug: Drug) -> i32 {
+        self.game.max_buyable(drug)
+    }
+
+    // Whether a drug can be bought or sold here today
+    pub fn is_available(&self, drug: Drug) -> bool {
+        self.market.is_available(drug)
+    }
+
+    // Whether the player is in the city with the bank
+    pub fn at_bank(&self) -> bool {
+        self.game.at_bank()
+    }
+
+    // The routes out of the current city
+    pub fn routes(&self) -> &[Route] {
+        self.game.routes()
+    }
+
+    // Today's price of a drug relative to the middle of its everyday range:
+    // below 1.0 is cheap, above 1.0 is expensive
+    pub fn price_ratio(&self, drug: Drug) -> f32 {
+        let Some(info) = self.rules.drug_info(drug) else {
+            return 1.0;
+        };
+        self.prices[&drug] as f32 / ((info.min_price + info.max_price) as f32 / 2.0)
+    }
+}
+
+impl Game {
+    // Takes a look at the game the way that a strategy sees it
+    pub fn observe(&self) -> Observation<'_> {
+        Observation {
+            player: &self.player,
+            prices: self.prices(),
+            market: self.market(),
+            days_left: (self.rules.start_days - self.player.day).max(0),
+            rules: &self.rules,
+            encounter: self.encounter.as_ref(),
+            shop: self.shop.as_ref(),
+            offer: self.offer.as_ref(),
+            game: self,
+        }
+    }
+}
+
+// Something that can play the game on its own
+pub trait Strategy {
+    // A short name for reports
+    fn name(&self) -> &str;
+
+    // The next action to take outside of a fight. It may be refused by the rules,
+    // in which case the strategy is simply asked again.
+    fn next_action(&mut self, observation: &Observation) -> Action;
+
+    // Whether to `Action::Fight` or `Action::Run` in the current round of a fight (or to take
+    // another way out: hiding, bribing or surrendering to the cops, or dropping drugs for
+    // rival dealers).
+    // By default: stand and fight while there is health (and a weapon, or a crew) to spare,
+    // otherwise pay off the cops if it is cheap, or else run or hide, whichever is likelier
+    // to work.
+    fn fight_or_run(&mut self, observation: &Observation, encounter: &Encounter) -> Action {
+        let rules = observation.rules;
+        let player = observation.player;
+        // The most that the enemy can hit for in one round
+        let worst_hit = 2 * encounter.enemy_count;
+        let armed = player.best_weapon(rules).is_some() || !player.henchmen.is_empty();
+        if armed && player.health > worst_hit {
+            Action::Fight
+        } else if encounter.enemy == Enemy::Cops
+            && 4 * rules.bribe_price(encounter.enemy_count, player.heat) <= player.cash
+        {
+            Action::Bribe
+        } else if rules.hide_chance(player.city) > rules.events.escape {
+            Action::Hide
+        } else {
+            Action::Run
+        }
+    }
+}
+
+// Asks a strategy for its next move in the game, whether or not there is a fight going on
+pub fn choose_action(strategy: &mut dyn Strategy, game: &Game) -> Action {
+    let observation = game.observe();
+    match observation.encounter {
+        Some(encounter) => strategy.fight_or_run(&observation, encounter),
+        None => strategy.next_action(&observation),
+    }
+}
+
+// The names that `strategy_by_name` understands
+pub const STRATEGY_NAMES: &[&str] = &["random", "greedy", "debt-first"];
+
+// Builds one of the reference strategies by name, seeding any randomness it uses from `seed`
+pub fn strategy_by_name(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy::new(seed))),
+        "greedy" => Some(Box::new(GreedyStrategy::new())),
+        "debt-first" => Some(Box::new(DebtFirstStrategy::new())),
+        _ => None,
+    }
+}
+
+// Builds the random number generator for a strategy. It runs on its own stream so that
+// a strategy seeded like the game does not mirror the game's own random decisions.
+fn strategy_rng(seed: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+    rng
+}
+
+// Plays completely at random: a baseline that any sensible strategy should beat
+pub struct RandomStrategy {
+    rng: ChaCha8Rng,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        RandomStrategy {
+            rng: strategy_rng(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_action(&mut self, observation: &Observation) -> Action {
+        // Buy anything on offer, or nothing at all
+        if observation.shop.is_some() {
+            let mut items = vec![ShopItem::TrenchCoat, ShopItem::Medicine, ShopItem::Ammo];
+            items.extend((0..observation.rules.weapons.len()).map(ShopItem::Weapon));
+            items.extend((0..observation.rules.armor.len()).map(ShopItem::Armor));
+            return match items.choose(&mut self.rng) {
+                Some(item) if self.rng.gen_bool(0.5) => Action::ShopBuy(*item),
+                _ => Action::LeaveShop,
+            };
+        }
+        // Take strangers up on their offers at the flip of a coin
+        if observation.offer.is_some() && self.rng.gen_bool(0.5) {
+            return Action::AcceptOffer;
+        }
+        let player = observation.player;
+        let drug = *observation.rules.drug_list().choose(&mut self.rng).unwrap();
+        match self.rng.gen_range(0..5) {
+            0 => {
+                let max = observation.max_buyable(drug);
+                if max > 0 {
+                    return Action::Buy {
+                        drug,
+                        qty: self.rng.gen_range(1..=max),
+                    };
+                }
+            }
+            1 => {
+                let owned = player.inventory[&drug];
+                if owned > 0 && observation.is_available(drug) {
+                    return Action::Sell {
+                        drug,
+                        qty: self.rng.gen_range(1..=owned),
+                    };
+                }
+            }
+            2 if player.debt > 0 && player.cash > 0 => {
+                let most = player.debt.min(player.cash);
+                return Action::PayDebt(self.rng.gen_range(1..=most));
+            }
+            3 if self.rng.gen_bool(0.2) => return Action::ShopAround,
+            3 if player.cash >= observation.rules.henchmen.fee && self.rng.gen_bool(0.2) => {
+                return Action::HireHenchman;
+            }
+            4 if observation.at_bank() => {
+                if player.bank > 0 && self.rng.gen_bool(0.5) {
+                    return Action::Withdraw(self.rng.gen_range(1..=player.bank));
+                }
+                if player.cash > 0 {
+                    return Action::Deposit(self.rng.gen_range(1..=player.cash));
+                }
+            }
+            _ => {}
+        }
+        // Nothing else to do: move on to another day somewhere
+        match observation.routes().choose(&mut self.rng) {
+            Some(route) => Action::Travel(route.to),
+            None => Action::Travel(player.city),
+        }
+    }
+
+    // Any of the moves open against this enemy, picked at random
+    fn fight_or_run(&mut self, _observation: &Observation, encounter: &Encounter) -> Action {
+        let moves: &[Action] = match encounter.enemy {
+            Enemy::Cops => &[
+                Action::Fight,
+                Action::Run,
+                Action::Hide,
+                Action::Bribe,
+                Action::Surrender,
+            ],
+            Enemy::RivalDealers => &[Action::Fight, Action::Run, Action::Hide, Action::DropDrugs],
+            Enemy::Enforcers => &[Action::Fight, Action::Run, Action::Hide],
+        };
+        *moves.choose(&mut self.rng).unwrap()
+    }
+}
+
+// Buys low and sells high: sells anything trading above the middle of its everyday range,
+// fills the trench coat with whatever is cheapest, and only pays the loan shark when the
+// debt is about to get out of hand
+pub struct GreedyStrategy {
+    buy_below: f32,  // Buy a drug when its price ratio is below this
+    sell_above: f32, // Sell a drug when its price ratio is above this
+}
+
+impl GreedyStrategy {
+    pub fn new() -> Self {
+        GreedyStrategy {
+            buy_below: 0.6,
+            sell_above: 1.0,
+        }
+    }
+
+    // Spends spare cash at the black market on a better weapon, armor, ammunition, a bigger
+    // coat or medicine
+    fn shop(&self, observation: &Observation, offer: &ShopOffer) -> Action {
+        let player = observation.player;
+        let rules = observation.rules;
+        // The hardest-hitting weapon that beats the one carried and leaves plenty of cash over
+        let weapon = (0..offer.weapon_prices.len())
+            .filter(|weapon| {
+                self.is_upgrade(observation, *weapon)
+                    && player.cash >= 2 * offer.weapon_prices[*weapon]
+            })
+            .max_by(|a, b| {
+                rules.weapons[*a]
+                    .expected_damage()
+                    .total_cmp(&rules.weapons[*b].expected_damage())
+            });
+        // The toughest armor going, when there is none worn, that leaves plenty of cash over
+        let armor = (0..offer.armor_prices.len())
+            .filter(|armor| player.armor.is_none() && player.cash >= 3 * offer.armor_prices[*armor])
+            .max_by(|a, b| rules.armor[*a].absorb.total_cmp(&rules.armor[*b].absorb));
+        if player.health <= rules.max_health / 2 && player.cash >= offer.med_price {
+            Action::ShopBuy(ShopItem::Medicine)
+        } else if let Some(weapon) = weapon {
+            Action::ShopBuy(ShopItem::Weapon(weapon))
+        } else if let Some(armor) = armor {
+            Action::ShopBuy(ShopItem::Armor(armor))
+        } else if self.needs_ammo(observation) && player.cash >= 4 * offer.ammo_price {
+            Action::ShopBuy(ShopItem::Ammo)
+        } else if player.cash >= 4 * offer.coat_price {
+            Action::ShopBuy(ShopItem::TrenchCoat)
+        } else {
+            Action::LeaveShop
+        }
+    }
+
+    // Whether the black market has anything worth the trip: something that `shop` would buy
+    // even at the highest price that it can be offered at
+    fn wants_to_shop(&self, observation: &Observation) -> bool {
+        let player = observation.player;
+        // Melee weapons wear out, so an unarmed player waits until a firearm is affordable
+        let cheapest_firearm = observation
+            .rules
+            .weapons
+            .iter()
+            .filter(|info| info.ammo > 0)
+            .map(|info| info.highest_price())
+            .min();
+        (player.health <= observation.rules.max_health / 2 && player.cash >= *MEDICINE_PRICES.end())
+            || (player.weapons.is_empty()
+                && cheapest_firearm.is_some_and(|price| player.cash >= 2 * price))
+            || (self.needs_ammo(observation) && player.cash >= 4 * AMMO_PRICES.end())
+            || player.cash >= 4 * COAT_PRICES.end()
+    }
+
+    // Whether to take on another henchman: a couple of them are worth their wages while there
+    // is time left to make use of them and cash to keep paying them
+    fn wants_henchman(&self, observation: &Observation) -> bool {
+        let terms = &observation.rules.henchmen;
+        observation.player.henchmen.len() < 2
+            && observation.days_left >= 10
+            && observation.player.cash >= terms.fee + 50 * terms.wage
+    }
+
+    // Whether a weapon hits harder than the best one carried (loaded or not)
+    fn is_upgrade(&self, observation: &Observation, weapon: usize) -> bool {
+        let rules = observation.rules;
+        let best = observation
+            .player
+            .weapons
+            .iter()
+            .filter_map(|carried| rules.weapon(carried.kind))
+            .map(|info| info.expected_damage())
+            .fold(0.0, f64::max);
+        rules
+            .weapon(weapon)
+            .is_some_and(|info| info.expected_damage() > best)
+    }
+
+    // Whether a firearm carried is running low on ammunition
+    fn needs_ammo(&self, observation: &Observation) -> bool {
+        let rules = observation.rules;
+        let player = observation.player;
+        player.ammo < rules.ammo_box
+            && player
+                .weapons
+                .iter()
+                .filter_map(|carried| rules.weapon(carried.kind))
+                .any(|info| info.ammo > 0)
+            && player.free_space(rules) >= rules.ammo_space(rules.ammo_box)
+    }
+}
+
+impl Default for GreedyStrategy {
+    fn default() -> Self {
+        GreedyStrategy::new()
+    }
+}
+
+impl Strategy for GreedyStrategy {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn next_action(&mut self, observation: &Observation) -> Action {
+        if let Some(offer) = observation.shop {
+            return self.shop(observation, offer);
+        }
+        let player = observation.player;
+        // A stranger's gun or coat is worth it on the same terms as the black market's
+        if let Some(offer) = observation.offer {
+            let worth_it = match offer.item {
+                ShopItem::Weapon(weapon) => {
+                    self.is_upgrade(observation, weapon) && player.cash >= 2 * offer.price
+                }
+                ShopItem::TrenchCoat => player.cash >= 4 * offer.price,
+                ShopItem::Ammo | ShopItem::Armor(_) | ShopItem::Medicine => false,
+            };
+            if worth_it {
+                return Action::AcceptOffer;
+            }
+        }
+        // Sell anything that is trading high (or everything on the last day)
+        for drug in observation.rules.drug_list() {
+            let owned = player.inventory[&drug];
+            if owned > 0
+                && observation.is_available(drug)
+                && (observation.price_ratio(drug) >= self.sell_above || observation.days_left == 0)
+            {
+                return Action::Sell { drug, qty: owned };
+            }
+        }
+        // Pay the lender before the enforcers come: when the payment is due, or before the
+        // debt grows past the point where they want it all back
+        let rules = observation.rules;
+        let rate = rules.lender_interest(player.lender);
+        let tomorrow = (player.debt as f32 * (1.0 + rate)).ceil() as i32;
+        let due = player.day >= player.due_day(rules) || tomorrow > player.max_debt(rules);
+        if player.debt > 0 && due && player.cash > 0 {
+            return Action::PayDebt(player.debt.min(player.cash));
+        }
+        if self.wants_to_shop(observation) {
+            return Action::ShopAround;
+        }
+        if self.wants_henchman(observation) {
+            return Action::HireHenchman;
+        }
+        // Fill up on the cheapest drug, if anything is cheap enough (and there is time to sell it)
+        if observation.days_left > 0 {
+            let cheapest = observation
+                .rules
+                .drug_list()
+                .into_iter()
+                .filter(|drug| observation.max_buyable(*drug) > 0)
+                .min_by(|a, b| {
+                    observation
+                        .price_ratio(*a)
+                        .total_cmp(&observation.price_ratio(*b))
+                });
+            if let Some(drug) = cheapest
+                && observation.price_ratio(drug) < self.buy_below
+            {
+                return Action::Buy {
+                    drug,
+                    qty: observation.max_buyable(drug),
+                };
+            }
+        }
+        // Try the next city on the map that can be reached (and afforded) from here
+        let cities = rules.city_list();
+        let position = |city| cities.iter().position(|c| *c == city).unwrap_or(0);
+        let here = position(player.city);
+        let next = observation
+            .routes()
+            .iter()
+            .filter(|route| route.cost <= player.cash)
+            .min_by_key(|route| (position(route.to) + cities.len() - here - 1) % cities.len());
+        match next.or(observation.routes().first()) {
+            Some(route) => Action::Travel(route.to),
+            None => Action::Travel(player.city),
+        }
+    }
+}
+
+// Pays the loan shark back before anything else, keeping only enough cash to trade with,
+// and otherwise trades like the greedy strategy
+pub struct DebtFirstStrategy {
+    greedy: GreedyStrategy,
+    float: i32, // Cash held back from the loan shark to keep trading
+}
+
+impl DebtFirstStrategy {
+    pub fn new() -> Self {
+        DebtFirstStrategy {
+            greedy: GreedyStrategy::new(),
+            float: 1000,
+        }
+    }
+}
+
+impl Default for DebtFirstStrategy {
+    fn default() -> Self {
+        DebtFirstStrategy::new()
+    }
+}
+
+impl Strategy for DebtFirstStrategy {
+    fn name(&self) -> &str {
+        "debt-first"
+    }
+
+    fn next_action(&mut self, observation: &Observation) -> Action {
+        let player = observation.player;
+        let spare = player.cash - self.float;
+        if observation.shop.is_none() && player.debt > 0 && spare > 0 {
+            return Action::PayDebt(player.debt.min(spare));
+        }
+        self.greedy.next_action(observation)
+    }
+}